@@ -4,15 +4,52 @@ pub mod scanner {
     pub const SCAN_COUNTS: &str = "scanner_scan_counts";
     pub const PARSE_COUNTS: &str = "scanner_parse_counts";
     pub const FILTER_COUNTS: &str = "scanner_filter_counts";
+
+    pub const WARNING: &str = "scanner_warning";
 }
 
 pub mod deletion {
+    pub const STATUS: &str = "deletion_status";
+
     pub const CATEGORY_START: &str = "deletion_category_start";
     pub const CATEGORY_COMPLETE: &str = "deletion_category_complete";
+    pub const PROGRESS: &str = "deletion_progress";
 }
 
 pub mod status_values {
     pub const SCAN_START: &str = "scan_start";
     pub const PARSE_START: &str = "parse_start";
     pub const FILTER_START: &str = "filter_start";
+    pub const CANCELLED: &str = "cancelled";
+}
+
+pub mod quarantine {
+    pub const DIR_SUFFIX: &str = ".quarantine";
+    pub const MANIFEST_FILE: &str = "manifest.json";
+    /// Entries appended one per line until the manifest is next saved.
+    pub const JOURNAL_FILE: &str = "manifest.journal";
+    pub const DEFAULT_GRACE_PERIOD_SECS: u64 = 7 * 24 * 60 * 60; // 7 days
+}
+
+pub mod placeholder {
+    pub const SIZE: u32 = 16;
+    pub const DEFAULT_COLOR: [u8; 3] = [0, 0, 0];
+}
+
+pub mod storyboard {
+    pub const MAX_ANIMATION_FRAMES: usize = 10_000;
+}
+
+pub mod layout {
+    pub const SONGS_DIR: &str = "Songs";
+    pub const SKINS_DIR: &str = "Skins";
+    /// Folders that, next to `Songs/`, mark an osu!stable installation.
+    pub const INSTALL_DIRS: [&str; 4] = ["Skins", "Replays", "Screenshots", "Data"];
+}
+
+pub mod hitsound {
+    /// Formats osu! looks for when loading a beatmap's custom samples.
+    pub const SAMPLE_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+    /// Upper bound on slider repeats, each of which adds an edge sample.
+    pub const MAX_SLIDER_SLIDES: u32 = 10_000;
 }
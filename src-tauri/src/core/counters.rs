@@ -1,6 +1,6 @@
 use std::{
     collections::HashMap,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
 };
 
 use crate::core::models::FileType;
@@ -25,12 +25,42 @@ impl CommonCounterState {
     }
 }
 
+pub struct DeletionCounterState {
+    count: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl DeletionCounterState {
+    pub fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn increment(&self, bytes: u64) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn get_and_reset(&self) -> (usize, u64) {
+        (
+            self.count.swap(0, Ordering::Relaxed),
+            self.bytes.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
 pub struct FilterCounterState {
     background_video: AtomicUsize,
     background_image: AtomicUsize,
     storyboard: AtomicUsize,
+    storyboard_audio: AtomicUsize,
     skin_element: AtomicUsize,
     hitsound: AtomicUsize,
+    keysound: AtomicUsize,
+    protected: AtomicUsize,
+    unreferenced: AtomicUsize,
     other: AtomicUsize,
 }
 
@@ -40,8 +70,12 @@ impl FilterCounterState {
             background_video: AtomicUsize::new(0),
             background_image: AtomicUsize::new(0),
             storyboard: AtomicUsize::new(0),
+            storyboard_audio: AtomicUsize::new(0),
             skin_element: AtomicUsize::new(0),
             hitsound: AtomicUsize::new(0),
+            keysound: AtomicUsize::new(0),
+            protected: AtomicUsize::new(0),
+            unreferenced: AtomicUsize::new(0),
             other: AtomicUsize::new(0),
         }
     }
@@ -51,8 +85,12 @@ impl FilterCounterState {
             FileType::BackgroundVideo => &self.background_video,
             FileType::BackgroundImage => &self.background_image,
             FileType::Storyboard => &self.storyboard,
+            FileType::StoryboardAudio => &self.storyboard_audio,
             FileType::SkinElement => &self.skin_element,
             FileType::Hitsound => &self.hitsound,
+            FileType::Keysound => &self.keysound,
+            FileType::Protected => &self.protected,
+            FileType::Unreferenced => &self.unreferenced,
             FileType::Other => &self.other,
         }
     }
@@ -76,8 +114,15 @@ impl FilterCounterState {
             self.reset_counter(&self.background_image),
         );
         counts.insert("storyboard", self.reset_counter(&self.storyboard));
+        counts.insert(
+            "storyboard_audio",
+            self.reset_counter(&self.storyboard_audio),
+        );
         counts.insert("skin_element", self.reset_counter(&self.skin_element));
         counts.insert("hitsound", self.reset_counter(&self.hitsound));
+        counts.insert("keysound", self.reset_counter(&self.keysound));
+        counts.insert("protected", self.reset_counter(&self.protected));
+        counts.insert("unreferenced", self.reset_counter(&self.unreferenced));
         counts.insert("other", self.reset_counter(&self.other));
         counts
     }
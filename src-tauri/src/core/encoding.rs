@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+/// Decodes a whole `.osu` or `.osb` file, stripping a UTF-8 BOM. Files that
+/// are not valid UTF-8 get one legacy encoding for all of their lines:
+/// Shift-JIS or Windows-1252, whichever mentions more of `file_names`.
+///
+/// Many Windows-1252 names are also valid Shift-JIS (`Pokémon.jpg` reads as
+/// `Pok駑on.jpg`), so validity alone cannot tell the two apart. `file_names`
+/// are the lower-cased names of the files next to the beatmap and are only
+/// listed when a legacy encoding has to be picked.
+pub fn decode_file<'a, 'n, F>(bytes: &'a [u8], file_names: F) -> Cow<'a, str>
+where
+    F: FnOnce() -> Vec<&'n str>,
+{
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+
+    let windows_1252 = WINDOWS_1252.decode_without_bom_handling(bytes).0;
+    let shift_jis = match SHIFT_JIS.decode_without_bom_handling_and_without_replacement(bytes) {
+        Some(text) => text,
+        None => return windows_1252,
+    };
+
+    // ASCII names read the same either way
+    let file_names: Vec<&str> = file_names()
+        .into_iter()
+        .filter(|name| !name.is_ascii())
+        .collect();
+    if mentions(&windows_1252, &file_names) > mentions(&shift_jis, &file_names) {
+        windows_1252
+    } else {
+        shift_jis
+    }
+}
+
+fn mentions(text: &str, file_names: &[&str]) -> usize {
+    let text = text.to_lowercase();
+    file_names
+        .iter()
+        .filter(|name| text.contains(**name))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_utf8_and_strips_the_bom() {
+        let text = decode_file("\u{FEFF}0,0,\"背景.jpg\",0,0".as_bytes(), Vec::new);
+        assert_eq!(text, "0,0,\"背景.jpg\",0,0");
+        assert!(matches!(text, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn picks_windows_1252_for_accented_names() {
+        let bytes = b"[Events]\r\n0,0,\"Pok\xE9mon.jpg\",0,0\r\nSprite,Foreground,Centre,\"\xDCber.png\",0,0\r\n";
+        let text = decode_file(bytes, || vec!["pokémon.jpg", "über.png", "audio.mp3"]);
+        assert!(text.contains("\"Pokémon.jpg\""));
+        assert!(text.contains("\"Über.png\""));
+    }
+
+    #[test]
+    fn picks_shift_jis_for_japanese_names() {
+        let (bytes, _, _) = SHIFT_JIS.encode("AudioFilename: 曲.mp3\r\n0,0,\"背景.jpg\",0,0\r\n");
+        let text = decode_file(&bytes, || vec!["背景.jpg", "曲.mp3"]);
+        assert_eq!(text, "AudioFilename: 曲.mp3\r\n0,0,\"背景.jpg\",0,0\r\n");
+
+        // Without matching files Shift-JIS is still the better guess
+        let text = decode_file(&bytes, Vec::new);
+        assert!(text.contains("背景.jpg"));
+    }
+
+    #[test]
+    fn picks_windows_1252_when_only_one_name_differs() {
+        let bytes = b"0,0,\"Se\xF1or.png\",0,0";
+        let text = decode_file(bytes, || vec!["señor.png"]);
+        assert_eq!(text, "0,0,\"Señor.png\",0,0");
+    }
+
+    #[test]
+    fn falls_back_to_windows_1252_for_invalid_shift_jis() {
+        let text = decode_file(b"Title:Caf\xE9 \xFF", Vec::new);
+        assert_eq!(text, "Title:Café ÿ");
+    }
+}
@@ -0,0 +1,223 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+
+/// A file reference found on an `[Events]` line of a `.osu` or `.osb` file.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    Background { path: &'a str },
+    Video { path: &'a str },
+    Sprite { path: &'a str },
+    Animation { path: &'a str, frame_count: usize },
+    Sample { path: &'a str },
+}
+
+/// Parses an event line in either its named (`Sprite,...`) or numeric
+/// (`4,...`) form. Lines that reference no file, such as breaks, colour
+/// changes and storyboard commands, yield `None`.
+pub fn parse_event(line: &str) -> Option<Event<'_>> {
+    let fields = split_fields(line);
+    let path = |index: usize| fields.get(index).copied().filter(|path| !path.is_empty());
+
+    match *fields.first()? {
+        // Background,startTime,filename,xOffset,yOffset
+        "0" | "Background" => Some(Event::Background { path: path(2)? }),
+        // Video,startTime,filename,xOffset,yOffset
+        "1" | "Video" => Some(Event::Video { path: path(2)? }),
+        // Sprite,layer,origin,filepath,x,y
+        "4" | "Sprite" => Some(Event::Sprite { path: path(3)? }),
+        // Animation,layer,origin,filepath,x,y,frameCount,frameDelay,looptype
+        "6" | "Animation" => Some(Event::Animation {
+            path: path(3)?,
+            frame_count: fields.get(6)?.parse().ok()?,
+        }),
+        // Sample,time,layer_num,filepath,volume
+        "5" | "Sample" => Some(Event::Sample { path: path(3)? }),
+        _ => None,
+    }
+}
+
+/// `$name=value` definitions from a storyboard's `[Variables]` section.
+#[derive(Default)]
+pub struct Variables {
+    /// Kept longest name first so `$ab` is not clobbered by `$a`.
+    definitions: Vec<(String, String)>,
+}
+
+impl Variables {
+    pub fn define(&mut self, line: &str) -> Option<()> {
+        let (name, value) = line.trim().split_once('=')?;
+        if !name.starts_with('$') || name.len() < 2 {
+            return None;
+        }
+
+        self.definitions.retain(|(defined, _)| defined != name);
+        self.definitions.push((name.to_owned(), value.to_owned()));
+        self.definitions
+            .sort_by_key(|(defined, _)| Reverse(defined.len()));
+        None
+    }
+
+    /// Replaces every defined `$name` in `line` with its value.
+    pub fn substitute<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if self.definitions.is_empty() || !line.contains('$') {
+            return Cow::Borrowed(line);
+        }
+
+        let mut line = line.to_owned();
+        for (name, value) in &self.definitions {
+            line = line.replace(name.as_str(), value);
+        }
+        Cow::Owned(line)
+    }
+}
+
+/// Splits a line on commas that are not inside double quotes, trimming
+/// whitespace (including a trailing CR) and the quotes around each field.
+fn split_fields(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(unquote(&line[start..index]));
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(unquote(&line[start..]));
+    fields
+}
+
+fn unquote(field: &str) -> &str {
+    let field = field.trim();
+    field
+        .strip_prefix('"')
+        .and_then(|field| field.strip_suffix('"'))
+        .unwrap_or(field)
+        .trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_backgrounds() {
+        assert_eq!(
+            parse_event(r#"0,0,"bg.jpg",0,0"#),
+            Some(Event::Background { path: "bg.jpg" })
+        );
+        assert_eq!(
+            parse_event(r#"0,150,"bg.jpg""#),
+            Some(Event::Background { path: "bg.jpg" })
+        );
+        assert_eq!(
+            parse_event("Background,0,bg.png"),
+            Some(Event::Background { path: "bg.png" })
+        );
+    }
+
+    #[test]
+    fn parses_videos() {
+        assert_eq!(
+            parse_event(r#"Video,-200,"video.avi""#),
+            Some(Event::Video { path: "video.avi" })
+        );
+        assert_eq!(
+            parse_event(r#"1,0,"video.mp4",0,0"#),
+            Some(Event::Video { path: "video.mp4" })
+        );
+    }
+
+    #[test]
+    fn parses_sprites_and_animations() {
+        assert_eq!(
+            parse_event(r#"Sprite,Foreground,Centre,"sb\star.png",320,240"#),
+            Some(Event::Sprite {
+                path: r"sb\star.png"
+            })
+        );
+        assert_eq!(
+            parse_event("4,3,1,sb/dot.png,320,240"),
+            Some(Event::Sprite { path: "sb/dot.png" })
+        );
+        assert_eq!(
+            parse_event(r#"Animation,Fail,Centre,"sb/anim.png",320,240,12,50,LoopForever"#),
+            Some(Event::Animation {
+                path: "sb/anim.png",
+                frame_count: 12
+            })
+        );
+        assert_eq!(
+            parse_event("6,0,1,sb/anim.png,320,240,3,100"),
+            Some(Event::Animation {
+                path: "sb/anim.png",
+                frame_count: 3
+            })
+        );
+    }
+
+    #[test]
+    fn parses_samples() {
+        assert_eq!(
+            parse_event(r#"Sample,56315,0,"sb/sfx/boom.wav",70"#),
+            Some(Event::Sample {
+                path: "sb/sfx/boom.wav"
+            })
+        );
+        assert_eq!(
+            parse_event("5,1000,3,hit.ogg"),
+            Some(Event::Sample { path: "hit.ogg" })
+        );
+    }
+
+    #[test]
+    fn handles_whitespace_commas_and_line_endings() {
+        assert_eq!(
+            parse_event("  0,0,\"my bg.jpg\",0,0\r"),
+            Some(Event::Background { path: "my bg.jpg" })
+        );
+        assert_eq!(
+            parse_event(r#"Sprite,Background,TopLeft,"sb/a, b.png",0,0"#),
+            Some(Event::Sprite {
+                path: "sb/a, b.png"
+            })
+        );
+        assert_eq!(
+            parse_event("0, 0, bg.jpg ,0,0"),
+            Some(Event::Background { path: "bg.jpg" })
+        );
+    }
+
+    #[test]
+    fn substitutes_variables() {
+        let mut variables = Variables::default();
+        variables.define("$dir=sb/particles");
+        variables.define("$d=\"dot.png\"");
+        variables.define("$layer=Foreground");
+
+        let line = variables.substitute("Sprite,$layer,Centre,\"$dir/glow.png\",320,240");
+        assert_eq!(
+            parse_event(&line),
+            Some(Event::Sprite {
+                path: "sb/particles/glow.png"
+            })
+        );
+        let line = variables.substitute("Sprite,$layer,Centre,$d,320,240");
+        assert_eq!(parse_event(&line), Some(Event::Sprite { path: "dot.png" }));
+    }
+
+    #[test]
+    fn ignores_lines_without_files() {
+        assert_eq!(parse_event("2,1000,5000"), None);
+        assert_eq!(parse_event("3,100,163,162,255"), None);
+        assert_eq!(parse_event(" F,0,1000,2000,0,1"), None);
+        assert_eq!(parse_event("//Storyboard Layer 0 (Background)"), None);
+        assert_eq!(parse_event(r#"0,0,"",0,0"#), None);
+        assert_eq!(parse_event(""), None);
+    }
+}
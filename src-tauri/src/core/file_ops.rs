@@ -0,0 +1,16 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Renames `from` to `to`, falling back to copy and remove when both sides
+/// live on different filesystems.
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(from, to)?;
+    fs::remove_file(from).inspect_err(|_| {
+        let _ = fs::remove_file(to);
+    })
+}
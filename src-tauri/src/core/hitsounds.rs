@@ -0,0 +1,639 @@
+use ahash::AHashSet;
+
+use crate::core::consts::hitsound;
+
+const HIT_WHISTLE: u8 = 2;
+const HIT_FINISH: u8 = 4;
+const HIT_CLAP: u8 = 8;
+
+const TYPE_SLIDER: u8 = 2;
+const TYPE_SPINNER: u8 = 8;
+const TYPE_HOLD: u8 = 128;
+
+/// osu! picks up a sample point this many milliseconds after a sample.
+const SAMPLE_LENIENCE: f64 = 5.0;
+const DEFAULT_SLIDER_MULTIPLIER: f64 = 1.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleSet {
+    Normal,
+    Soft,
+    Drum,
+}
+
+impl SampleSet {
+    /// `0` means "inherit" and maps to `None`.
+    fn from_id(id: &str) -> Option<Self> {
+        match id.trim() {
+            "1" => Some(SampleSet::Normal),
+            "2" => Some(SampleSet::Soft),
+            "3" => Some(SampleSet::Drum),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "Normal" => Some(SampleSet::Normal),
+            "Soft" => Some(SampleSet::Soft),
+            "Drum" => Some(SampleSet::Drum),
+            _ => None,
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            SampleSet::Normal => "normal",
+            SampleSet::Soft => "soft",
+            SampleSet::Drum => "drum",
+        }
+    }
+}
+
+struct TimingSample {
+    time: f64,
+    /// Milliseconds per beat, or a negative slider velocity percentage on
+    /// inherited points.
+    beat_length: f64,
+    uninherited: bool,
+    set: Option<SampleSet>,
+    index: u32,
+}
+
+/// When a sample plays. Slider times depend on timing points that may not
+/// have been read yet, so they are worked out at the end.
+#[derive(Clone, Copy)]
+enum SampleTime {
+    At(f64),
+    /// Edge `edge` of a slider, counting the head as `0`.
+    SliderEdge {
+        start: f64,
+        length: f64,
+        edge: u32,
+    },
+    /// Slide and tick sounds, played over the whole slider body.
+    SliderBody {
+        start: f64,
+        length: f64,
+        slides: u32,
+    },
+}
+
+/// Sample settings of a single hit object edge before the timing point
+/// defaults are applied.
+struct PendingSample {
+    time: SampleTime,
+    sounds: u8,
+    normal_set: Option<SampleSet>,
+    addition_set: Option<SampleSet>,
+    index: u32,
+}
+
+#[derive(Default, Clone, Copy)]
+struct HitSample {
+    normal_set: Option<SampleSet>,
+    addition_set: Option<SampleSet>,
+    index: u32,
+}
+
+/// Sample files a difficulty can load, relative to its beatmap folder.
+pub struct SampleFiles {
+    /// Named after a sample set and index, e.g. `soft-hitclap2.wav`.
+    pub hitsounds: AHashSet<String>,
+    /// Given explicitly in the filename field of a hitSample.
+    pub custom: AHashSet<String>,
+}
+
+/// Collects the sample references of one `.osu` file and works out which
+/// files osu! can load for them, e.g. `soft-hitclap2.wav`.
+#[derive(Default)]
+pub struct HitsoundReferences {
+    default_set: Option<SampleSet>,
+    slider_multiplier: Option<f64>,
+    timing_points: Vec<TimingSample>,
+    samples: Vec<PendingSample>,
+    filenames: AHashSet<String>,
+}
+
+impl HitsoundReferences {
+    /// Handles a `[General]` line, picking up `SampleSet`.
+    pub fn add_general_line(&mut self, key: &str, value: &str) {
+        if key == "SampleSet" {
+            self.default_set = SampleSet::from_name(value);
+        }
+    }
+
+    /// Handles a `[Difficulty]` line, picking up `SliderMultiplier`.
+    pub fn add_difficulty_line(&mut self, key: &str, value: &str) {
+        if key == "SliderMultiplier" {
+            self.slider_multiplier = value.trim().parse().ok();
+        }
+    }
+
+    /// Handles `time,beatLength,meter,sampleSet,sampleIndex,volume,uninherited,...`.
+    pub fn add_timing_point(&mut self, line: &str) -> Option<()> {
+        let fields: Vec<&str> = line.split(',').collect();
+        let time = fields.first()?.trim().parse().ok()?;
+        let beat_length: f64 = fields.get(1)?.trim().parse().ok()?;
+        self.timing_points.push(TimingSample {
+            time,
+            beat_length,
+            uninherited: beat_length >= 0.0 && fields.get(6).map(|field| field.trim()) != Some("0"),
+            set: fields.get(3).and_then(|set| SampleSet::from_id(set)),
+            index: fields
+                .get(4)
+                .and_then(|index| index.trim().parse().ok())
+                .unwrap_or(1),
+        });
+        Some(())
+    }
+
+    /// Handles `x,y,time,type,hitSound,objectParams,hitSample`.
+    pub fn add_hit_object(&mut self, line: &str) -> Option<()> {
+        let fields: Vec<&str> = line.split(',').collect();
+        let time: f64 = fields.get(2)?.trim().parse().ok()?;
+        let object_type: u8 = fields.get(3)?.trim().parse().ok()?;
+        let sounds: u8 = fields.get(4)?.trim().parse().ok()?;
+
+        let hit_sample = if object_type & TYPE_SLIDER != 0 {
+            fields.get(10).copied()
+        } else if object_type & TYPE_SPINNER != 0 {
+            fields.get(6).copied()
+        } else if object_type & TYPE_HOLD != 0 {
+            // Hold notes write `endTime:hitSample`
+            fields
+                .get(5)
+                .and_then(|params| params.split_once(':'))
+                .map(|(_, hit_sample)| hit_sample)
+        } else {
+            fields.get(5).copied()
+        };
+        let hit_sample = hit_sample
+            .map(|hit_sample| self.parse_hit_sample(hit_sample))
+            .unwrap_or_default();
+
+        if object_type & TYPE_SLIDER != 0 {
+            self.add_slider_edges(&fields, time, sounds, hit_sample);
+        } else if object_type & TYPE_SPINNER != 0 {
+            // Spinners sound when they end
+            let end_time = fields
+                .get(5)
+                .and_then(|end_time| end_time.trim().parse().ok())
+                .unwrap_or(time);
+            self.push_sample(SampleTime::At(end_time), sounds, hit_sample);
+        } else {
+            self.push_sample(SampleTime::At(time), sounds, hit_sample);
+        }
+        Some(())
+    }
+
+    /// Reads `normalSet:additionSet:index:volume:filename`, recording the
+    /// explicit filename if there is one.
+    fn parse_hit_sample(&mut self, hit_sample: &str) -> HitSample {
+        let fields: Vec<&str> = hit_sample.split(':').collect();
+        if let Some(filename) = fields.get(4).map(|filename| filename.trim()) {
+            if !filename.is_empty() {
+                self.filenames.insert(filename.to_owned());
+            }
+        }
+        HitSample {
+            normal_set: fields.first().and_then(|set| SampleSet::from_id(set)),
+            addition_set: fields.get(1).and_then(|set| SampleSet::from_id(set)),
+            index: fields
+                .get(2)
+                .and_then(|index| index.trim().parse().ok())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Sliders carry per-edge sounds and sets in `edgeSounds` and `edgeSets`;
+    /// edges without an entry use the slider's own. A slider with `slides`
+    /// spans has `slides + 1` edges, each played at its own time.
+    fn add_slider_edges(&mut self, fields: &[&str], time: f64, sounds: u8, hit_sample: HitSample) {
+        let slides: u32 = fields
+            .get(6)
+            .and_then(|slides| slides.trim().parse().ok())
+            .unwrap_or(1)
+            .clamp(1, hitsound::MAX_SLIDER_SLIDES);
+        let length: f64 = fields
+            .get(7)
+            .and_then(|length| length.trim().parse().ok())
+            .unwrap_or(0.0);
+        let edge_sounds: Vec<u8> = fields
+            .get(8)
+            .map(|edges| {
+                edges
+                    .split('|')
+                    .filter_map(|sound| sound.trim().parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let edge_sets: Vec<(Option<SampleSet>, Option<SampleSet>)> = fields
+            .get(9)
+            .map(|edges| {
+                edges
+                    .split('|')
+                    .map(|sets| match sets.split_once(':') {
+                        Some((normal, addition)) => {
+                            (SampleSet::from_id(normal), SampleSet::from_id(addition))
+                        }
+                        None => (None, None),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for edge in 0..=slides {
+            let edge_sound = edge_sounds.get(edge as usize).copied().unwrap_or(sounds);
+            let (normal_set, addition_set) =
+                edge_sets.get(edge as usize).copied().unwrap_or_default();
+            let edge_sample = HitSample {
+                normal_set: normal_set.or(hit_sample.normal_set),
+                addition_set: addition_set.or(hit_sample.addition_set),
+                index: hit_sample.index,
+            };
+            let edge_time = SampleTime::SliderEdge {
+                start: time,
+                length,
+                edge,
+            };
+            self.push_sample(edge_time, edge_sound, edge_sample);
+        }
+        let body_time = SampleTime::SliderBody {
+            start: time,
+            length,
+            slides,
+        };
+        self.push_sample(body_time, sounds, hit_sample);
+    }
+
+    fn push_sample(&mut self, time: SampleTime, sounds: u8, hit_sample: HitSample) {
+        self.samples.push(PendingSample {
+            time,
+            sounds,
+            normal_set: hit_sample.normal_set,
+            addition_set: hit_sample.addition_set,
+            index: hit_sample.index,
+        });
+    }
+
+    /// Every file name that the collected references can make osu! load.
+    pub fn into_sample_files(mut self) -> SampleFiles {
+        self.timing_points.sort_by(|a, b| {
+            a.time
+                .partial_cmp(&b.time)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut names = AHashSet::new();
+        for sample in &self.samples {
+            match sample.time {
+                SampleTime::At(time) => {
+                    self.add_sample_names(sample, self.sample_point_at(time), false, &mut names);
+                }
+                SampleTime::SliderEdge {
+                    start,
+                    length,
+                    edge,
+                } => {
+                    let time = start + f64::from(edge) * self.span_duration(start, length);
+                    self.add_sample_names(sample, self.sample_point_at(time), false, &mut names);
+                }
+                SampleTime::SliderBody {
+                    start,
+                    length,
+                    slides,
+                } => {
+                    // Ticks pick up every sample point the body passes
+                    let end = start + f64::from(slides) * self.span_duration(start, length);
+                    for point in self.sample_points_between(start, end) {
+                        self.add_sample_names(sample, point, true, &mut names);
+                    }
+                }
+            }
+        }
+        SampleFiles {
+            hitsounds: names,
+            custom: self.filenames,
+        }
+    }
+
+    fn add_sample_names(
+        &self,
+        sample: &PendingSample,
+        timing_point: Option<&TimingSample>,
+        slider_body: bool,
+        names: &mut AHashSet<String>,
+    ) {
+        let normal_set = sample
+            .normal_set
+            .or(timing_point.and_then(|point| point.set))
+            .or(self.default_set)
+            .unwrap_or(SampleSet::Normal);
+        let addition_set = sample.addition_set.unwrap_or(normal_set);
+        let index = match sample.index {
+            0 => timing_point.map_or(1, |point| point.index),
+            index => index,
+        };
+        // Index 0 falls back to the skin's samples
+        if index == 0 {
+            return;
+        }
+
+        let mut sounds = Vec::new();
+        if slider_body {
+            sounds.push((normal_set, "sliderslide"));
+            sounds.push((normal_set, "slidertick"));
+            if sample.sounds & HIT_WHISTLE != 0 {
+                sounds.push((addition_set, "sliderwhistle"));
+            }
+        } else {
+            // The normal sound always plays, whatever the hitSound bits say
+            sounds.push((normal_set, "hitnormal"));
+            for (bit, sound) in [
+                (HIT_WHISTLE, "hitwhistle"),
+                (HIT_FINISH, "hitfinish"),
+                (HIT_CLAP, "hitclap"),
+            ] {
+                if sample.sounds & bit != 0 {
+                    sounds.push((addition_set, sound));
+                }
+            }
+        }
+
+        for (set, sound) in sounds {
+            names.extend(sample_file_names(set, sound, index));
+        }
+    }
+
+    /// The timing point whose sample settings apply at `time`; objects placed
+    /// before the first timing point use the first one.
+    fn sample_point_at(&self, time: f64) -> Option<&TimingSample> {
+        let after = self
+            .timing_points
+            .partition_point(|point| point.time <= time + SAMPLE_LENIENCE);
+        self.timing_points.get(after.saturating_sub(1))
+    }
+
+    /// The sample point at `start` and every one after it up to `end`.
+    fn sample_points_between(&self, start: f64, end: f64) -> Vec<Option<&TimingSample>> {
+        let first = self
+            .timing_points
+            .partition_point(|point| point.time <= start + SAMPLE_LENIENCE)
+            .saturating_sub(1);
+        let last = self
+            .timing_points
+            .partition_point(|point| point.time <= end + SAMPLE_LENIENCE);
+        match self.timing_points.get(first..last) {
+            Some(points) if !points.is_empty() => points.iter().map(Some).collect(),
+            _ => vec![self.timing_points.first()],
+        }
+    }
+
+    /// Milliseconds one slider span starting at `start` takes, from the beat
+    /// length and slider velocity in effect there.
+    fn span_duration(&self, start: f64, length: f64) -> f64 {
+        let after = self
+            .timing_points
+            .partition_point(|point| point.time <= start);
+        let in_effect = &self.timing_points[..after];
+
+        let beat_length = in_effect
+            .iter()
+            .rev()
+            .find(|point| point.uninherited)
+            .or_else(|| self.timing_points.iter().find(|point| point.uninherited))
+            .map_or(0.0, |point| point.beat_length);
+        // An inherited point after the last uninherited one changes the velocity
+        let velocity = match in_effect.last() {
+            Some(point) if !point.uninherited => (-100.0 / point.beat_length).clamp(0.1, 10.0),
+            _ => 1.0,
+        };
+        let multiplier = self
+            .slider_multiplier
+            .filter(|multiplier| *multiplier > 0.0)
+            .unwrap_or(DEFAULT_SLIDER_MULTIPLIER);
+
+        let duration = length / (multiplier * 100.0 * velocity) * beat_length;
+        if duration.is_finite() && duration > 0.0 {
+            duration
+        } else {
+            0.0
+        }
+    }
+}
+
+/// osu! omits the index for `1`, so index 2 of `soft-hitclap` is
+/// `soft-hitclap2.wav` while index 1 is `soft-hitclap.wav`.
+fn sample_file_names(set: SampleSet, sound: &str, index: u32) -> impl Iterator<Item = String> {
+    let stem = match index {
+        1 => format!("{}-{}", set.prefix(), sound),
+        index => format!("{}-{}{}", set.prefix(), sound, index),
+    };
+    hitsound::SAMPLE_EXTENSIONS
+        .iter()
+        .map(move |extension| format!("{}.{}", stem, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_files(sample_set: &str, timing_points: &[&str], hit_objects: &[&str]) -> SampleFiles {
+        let mut references = HitsoundReferences::default();
+        references.add_general_line("SampleSet", sample_set);
+        references.add_difficulty_line("SliderMultiplier", "1");
+        for line in timing_points {
+            references.add_timing_point(line);
+        }
+        for line in hit_objects {
+            references.add_hit_object(line);
+        }
+        references.into_sample_files()
+    }
+
+    /// The `.wav` names only, sorted; every sample also has an `.ogg` and
+    /// `.mp3` variant.
+    fn wav_names(samples: &SampleFiles) -> Vec<&str> {
+        let mut names: Vec<&str> = samples
+            .hitsounds
+            .iter()
+            .map(String::as_str)
+            .filter(|name| name.ends_with(".wav"))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn names_samples_by_set_and_index() {
+        let samples = sample_files(
+            "Soft",
+            &["0,500,4,0,1,100,1,0", "2000,-100,4,3,2,100,0,0"],
+            &["256,192,1000,1,2,0:0:0:0:", "256,192,2500,1,8,0:0:0:0:"],
+        );
+        assert_eq!(
+            wav_names(&samples),
+            [
+                "drum-hitclap2.wav",
+                "drum-hitnormal2.wav",
+                "soft-hitnormal.wav",
+                "soft-hitwhistle.wav",
+            ]
+        );
+        assert!(samples.hitsounds.contains("soft-hitnormal.ogg"));
+        assert!(samples.hitsounds.contains("drum-hitclap2.mp3"));
+    }
+
+    #[test]
+    fn applies_hit_sample_overrides() {
+        let samples = sample_files(
+            "Normal",
+            &["0,500,4,1,1,100,1,0"],
+            // Normal set soft, addition set drum and an explicit index 4
+            &["256,192,1000,1,4,2:3:4:0:"],
+        );
+        assert_eq!(
+            wav_names(&samples),
+            ["drum-hitfinish4.wav", "soft-hitnormal4.wav"]
+        );
+    }
+
+    #[test]
+    fn uses_the_first_timing_point_before_it_starts() {
+        let samples = sample_files(
+            "Normal",
+            &["1000,500,4,2,3,100,1,0", "5000,500,4,3,1,100,1,0"],
+            &["256,192,0,1,0,0:0:0:0:", "256,192,6000,1,0,0:0:0:0:"],
+        );
+        assert_eq!(
+            wav_names(&samples),
+            ["drum-hitnormal.wav", "soft-hitnormal3.wav"]
+        );
+    }
+
+    #[test]
+    fn skips_index_zero() {
+        let samples = sample_files(
+            "Soft",
+            &["0,500,4,2,0,100,1,0"],
+            &["256,192,1000,1,2,0:0:0:0:"],
+        );
+        assert!(samples.hitsounds.is_empty());
+    }
+
+    #[test]
+    fn resolves_slider_edges_and_body() {
+        let samples = sample_files(
+            "Normal",
+            &["0,500,4,2,1,100,1,0"],
+            &["100,100,1000,2,2,B|200:200,1,100,2|8,1:0|3:0,0:0:0:0:"],
+        );
+        assert_eq!(
+            wav_names(&samples),
+            [
+                "drum-hitclap.wav",
+                "drum-hitnormal.wav",
+                "normal-hitnormal.wav",
+                "normal-hitwhistle.wav",
+                "soft-sliderslide.wav",
+                "soft-slidertick.wav",
+                "soft-sliderwhistle.wav",
+            ]
+        );
+    }
+
+    #[test]
+    fn plays_spinners_at_their_end() {
+        let samples = sample_files(
+            "Soft",
+            &[
+                "0,500,4,0,1,100,1,0",
+                "3000,500,4,0,2,100,1,0",
+                "6000,500,4,0,3,100,1,0",
+            ],
+            &["256,192,1000,12,0,4000,0:0:0:0:"],
+        );
+        assert_eq!(wav_names(&samples), ["soft-hitnormal2.wav"]);
+    }
+
+    #[test]
+    fn picks_up_sample_points_just_after_the_object() {
+        let samples = sample_files(
+            "Soft",
+            &["0,500,4,0,1,100,1,0", "3000,500,4,0,2,100,1,0"],
+            &["256,192,2996,1,0,0:0:0:0:", "256,192,2994,1,0,0:0:0:0:"],
+        );
+        assert_eq!(
+            wav_names(&samples),
+            ["soft-hitnormal.wav", "soft-hitnormal2.wav"]
+        );
+    }
+
+    #[test]
+    fn times_slider_edges_from_their_spans() {
+        // Spans take 100 / (1 * 100 * 1) beats of 500ms, so the edges fall
+        // at 0, 500 and 1000 and the repeat at 500 sees the green line
+        let samples = sample_files(
+            "Soft",
+            &[
+                "0,500,4,2,1,100,1,0",
+                "500,-100,4,2,2,100,0,0",
+                "1000,-100,4,3,3,100,0,0",
+            ],
+            &["0,0,0,2,0,L|100:0,2,100,0|2|8,0:0|0:0|0:0,0:0:0:0:"],
+        );
+        assert_eq!(
+            wav_names(&samples),
+            [
+                "drum-hitclap3.wav",
+                "drum-hitnormal3.wav",
+                "drum-sliderslide3.wav",
+                "drum-slidertick3.wav",
+                "soft-hitnormal.wav",
+                "soft-hitnormal2.wav",
+                "soft-hitwhistle2.wav",
+                "soft-sliderslide.wav",
+                "soft-sliderslide2.wav",
+                "soft-slidertick.wav",
+                "soft-slidertick2.wav",
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_slider_velocity_to_edge_times() {
+        // Double velocity halves the span, so the tail lands at 250
+        let samples = sample_files(
+            "Normal",
+            &[
+                "0,500,4,1,1,100,1,0",
+                "0,-50,4,1,1,100,0,0",
+                "250,-50,4,1,2,100,0,0",
+                "500,-50,4,1,3,100,0,0",
+            ],
+            &["0,0,0,2,0,L|100:0,1,100,0|8,0:0|0:0,0:0:0:0:"],
+        );
+        let names = wav_names(&samples);
+        assert!(names.contains(&"normal-hitclap2.wav"));
+        assert!(!names.iter().any(|name| name.ends_with("3.wav")));
+    }
+
+    #[test]
+    fn collects_custom_samples() {
+        let samples = sample_files(
+            "Normal",
+            &["0,500,4,1,1,100,1,0"],
+            &[
+                "256,192,1000,1,0,0:0:0:70:clap.wav",
+                // Hold notes write `endTime:hitSample`
+                "64,192,1500,128,0,2000:0:0:0:0:key 1.ogg",
+                "256,192,3000,12,0,4000,0:0:0:0:spin.wav",
+            ],
+        );
+        let mut custom: Vec<&str> = samples.custom.iter().map(String::as_str).collect();
+        custom.sort_unstable();
+        assert_eq!(custom, ["clap.wav", "key 1.ogg", "spin.wav"]);
+        assert_eq!(wav_names(&samples), ["normal-hitnormal.wav"]);
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::consts::layout;
+
+/// Where a scan root sits in an osu!stable installation.
+pub enum OsuLayout {
+    /// The installation folder itself; only its `Songs/` folder is scanned.
+    InstallRoot { songs: PathBuf },
+    /// The user's `Skins/` folder or anything inside it.
+    Skins,
+    /// Anything else, usually `Songs/` or a single beatmap set.
+    Other,
+}
+
+impl OsuLayout {
+    pub fn detect(root: &Path) -> Self {
+        if is_install_root(root) {
+            if let Some(songs) = find_dir(root, layout::SONGS_DIR) {
+                return OsuLayout::InstallRoot { songs };
+            }
+        }
+
+        let in_skins = root.ancestors().any(|dir| {
+            let is_skins = dir
+                .file_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(layout::SKINS_DIR));
+            is_skins && dir.parent().is_some_and(is_install_root)
+        });
+        if in_skins {
+            return OsuLayout::Skins;
+        }
+        OsuLayout::Other
+    }
+}
+
+/// A folder holding `Songs/` next to at least one other osu! folder.
+fn is_install_root(dir: &Path) -> bool {
+    find_dir(dir, layout::SONGS_DIR).is_some()
+        && layout::INSTALL_DIRS
+            .iter()
+            .any(|name| find_dir(dir, name).is_some())
+}
+
+/// Looks up a sub-directory ignoring case, since installs copied over from
+/// Windows keep whatever casing the folders had there.
+fn find_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| {
+            entry.file_name().eq_ignore_ascii_case(name)
+                && entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+        })
+        .map(|entry| entry.path())
+}
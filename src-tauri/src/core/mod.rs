@@ -1,5 +1,17 @@
+pub mod cancellation;
 pub mod consts;
 pub mod counters;
+pub mod encoding;
+pub mod events;
+pub mod file_ops;
+pub mod hitsounds;
+pub mod layout;
 pub mod models;
+pub mod paths;
 pub mod patterns;
+pub mod placeholder;
 pub mod processor;
+pub mod quarantine;
+#[cfg(test)]
+pub mod test_utils;
+pub mod trash;
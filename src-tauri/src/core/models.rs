@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 use ahash::AHashMap;
 use ahash::AHashSet;
@@ -9,27 +12,300 @@ pub enum FileType {
     BackgroundVideo,
     BackgroundImage,
     Storyboard,
+    StoryboardAudio,
     Hitsound,
     SkinElement,
+    /// Custom samples of osu!mania difficulties, which the charts need.
+    Keysound,
+    /// A set's song audio or a `.osu` file; never offered for deletion.
+    Protected,
+    /// Files inside a beatmap set that nothing in the set references.
+    Unreferenced,
     Other,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FileType {
+    pub fn from_category(category: &str) -> Option<Self> {
+        match category {
+            "background_video" => Some(FileType::BackgroundVideo),
+            "background_image" => Some(FileType::BackgroundImage),
+            "storyboard" => Some(FileType::Storyboard),
+            "storyboard_audio" => Some(FileType::StoryboardAudio),
+            "skin_element" => Some(FileType::SkinElement),
+            "hitsound" => Some(FileType::Hitsound),
+            "keysound" => Some(FileType::Keysound),
+            "unreferenced" => Some(FileType::Unreferenced),
+            _ => None,
+        }
+    }
+
+    pub fn category(&self) -> &'static str {
+        match self {
+            FileType::BackgroundVideo => "background_video",
+            FileType::BackgroundImage => "background_image",
+            FileType::Storyboard => "storyboard",
+            FileType::StoryboardAudio => "storyboard_audio",
+            FileType::SkinElement => "skin_element",
+            FileType::Hitsound => "hitsound",
+            FileType::Keysound => "keysound",
+            FileType::Protected => "protected",
+            FileType::Unreferenced => "unreferenced",
+            FileType::Other => "other",
+        }
+    }
+
+    /// Whether files of this type may be removed to reclaim space.
+    pub fn is_deletable(&self) -> bool {
+        !matches!(self, FileType::Protected | FileType::Other)
+    }
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    Osu,
+    Taiko,
+    Catch,
+    Mania,
+}
+
+impl GameMode {
+    /// Maps the `[General] Mode` value of a `.osu` file.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "0" => Some(GameMode::Osu),
+            "1" => Some(GameMode::Taiko),
+            "2" => Some(GameMode::Catch),
+            "3" => Some(GameMode::Mania),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionMode {
+    #[default]
+    Permanent,
+    Trash,
+    Quarantine,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub path: PathBuf,
     pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub modified: Option<u64>,
+    pub inode: Option<u64>,
+    /// Folder of the beatmap set the file belongs to.
+    pub beatmap_set: Option<PathBuf>,
+}
+
+impl FileInfo {
+    pub fn from_metadata(path: PathBuf, metadata: &Metadata) -> Self {
+        Self {
+            path,
+            size: metadata.len(),
+            modified: modified_nanos(metadata),
+            inode: inode(metadata),
+            beatmap_set: None,
+        }
+    }
+
+    /// Whether `metadata` still describes the file seen during the scan.
+    pub fn is_unchanged(&self, metadata: &Metadata) -> bool {
+        self.size == metadata.len()
+            && self.modified == modified_nanos(metadata)
+            && (self.inode.is_none() || self.inode == inode(metadata))
+    }
+}
+
+fn modified_nanos(metadata: &Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+}
+
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &Metadata) -> Option<u64> {
+    None
 }
 
 #[derive(Debug, Default)]
 pub struct ScanResult {
+    pub root: PathBuf,
     pub total_size: u64,
     pub files: AHashMap<FileType, Vec<FileInfo>>,
+    pub beatmaps: Vec<Beatmap>,
+    pub sets: Vec<BeatmapSetInfo>,
+}
+
+impl ScanResult {
+    /// Recomputes the per-set totals from `files`; call it after files are
+    /// added or removed.
+    pub fn refresh_sets(&mut self) {
+        let mut sets: AHashMap<PathBuf, BeatmapSetInfo> = AHashMap::new();
+        for (file_type, files) in &self.files {
+            for file in files {
+                let Some(folder) = &file.beatmap_set else {
+                    continue;
+                };
+                let set = sets
+                    .entry(folder.clone())
+                    .or_insert_with(|| BeatmapSetInfo::new(folder));
+                set.total_size += file.size;
+                if file_type.is_deletable() {
+                    set.reclaimable_size += file.size;
+                }
+                let detail = set
+                    .categories
+                    .entry(file_type.category().to_owned())
+                    .or_default();
+                detail.total_size += file.size;
+                detail.total_count += 1;
+            }
+        }
+
+        // Beatmaps are sorted by path, so the first difficulty names the set
+        for beatmap in &self.beatmaps {
+            if let Some(set) = beatmap
+                .path
+                .parent()
+                .and_then(|folder| sets.get_mut(folder))
+            {
+                if set.title.is_empty() {
+                    set.title = beatmap.title.clone();
+                    set.artist = beatmap.artist.clone();
+                }
+                set.set_id = set.set_id.or(beatmap.beatmap_set_id);
+                if !set.modes.contains(&beatmap.mode) {
+                    set.modes.push(beatmap.mode);
+                }
+            }
+        }
+        self.sets = sets
+            .into_values()
+            .map(|mut set| {
+                set.modes.sort_unstable();
+                set
+            })
+            .collect();
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BeatmapDifficulty {
+    pub hp_drain_rate: Option<f32>,
+    pub circle_size: Option<f32>,
+    pub overall_difficulty: Option<f32>,
+    /// Old maps leave this out and use the overall difficulty instead.
+    pub approach_rate: Option<f32>,
+    pub slider_multiplier: Option<f32>,
+    pub slider_tick_rate: Option<f32>,
+}
+
+/// Metadata of a single difficulty, read from its `.osu` file.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Beatmap {
+    pub path: PathBuf,
+    pub audio_filename: Option<String>,
+    pub mode: GameMode,
+    /// Milliseconds into the song; `None` when osu! picks the preview point.
+    pub preview_time: Option<i64>,
+    pub title: String,
+    pub title_unicode: String,
+    pub artist: String,
+    pub artist_unicode: String,
+    pub creator: String,
+    pub version: String,
+    pub source: String,
+    pub tags: Vec<String>,
+    /// `None` for unsubmitted maps, which use `0` or `-1`.
+    pub beatmap_id: Option<i64>,
+    pub beatmap_set_id: Option<i64>,
+    pub difficulty: BeatmapDifficulty,
+}
+
+impl Beatmap {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Applies a `key: value` line from the `[General]`, `[Metadata]` or
+    /// `[Difficulty]` section.
+    pub fn set_field(&mut self, section: &str, key: &str, value: &str) {
+        let id = || value.parse().ok().filter(|id: &i64| *id > 0);
+        let number = || value.parse().ok();
+        match (section, key) {
+            ("General", "AudioFilename") => self.audio_filename = Some(value.to_owned()),
+            ("General", "Mode") => self.mode = GameMode::from_id(value).unwrap_or_default(),
+            ("General", "PreviewTime") => {
+                self.preview_time = value.parse().ok().filter(|time: &i64| *time >= 0)
+            }
+            ("Metadata", "Title") => self.title = value.to_owned(),
+            ("Metadata", "TitleUnicode") => self.title_unicode = value.to_owned(),
+            ("Metadata", "Artist") => self.artist = value.to_owned(),
+            ("Metadata", "ArtistUnicode") => self.artist_unicode = value.to_owned(),
+            ("Metadata", "Creator") => self.creator = value.to_owned(),
+            ("Metadata", "Version") => self.version = value.to_owned(),
+            ("Metadata", "Source") => self.source = value.to_owned(),
+            ("Metadata", "Tags") => {
+                self.tags = value.split_whitespace().map(str::to_owned).collect()
+            }
+            ("Metadata", "BeatmapID") => self.beatmap_id = id(),
+            ("Metadata", "BeatmapSetID") => self.beatmap_set_id = id(),
+            ("Difficulty", "HPDrainRate") => self.difficulty.hp_drain_rate = number(),
+            ("Difficulty", "CircleSize") => self.difficulty.circle_size = number(),
+            ("Difficulty", "OverallDifficulty") => self.difficulty.overall_difficulty = number(),
+            ("Difficulty", "ApproachRate") => self.difficulty.approach_rate = number(),
+            ("Difficulty", "SliderMultiplier") => self.difficulty.slider_multiplier = number(),
+            ("Difficulty", "SliderTickRate") => self.difficulty.slider_tick_rate = number(),
+            _ => {}
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct ScanContext {
     pub backgrounds: AHashSet<PathBuf>,
     pub storyboard_elements: AHashSet<PathBuf>,
+    pub storyboard_audio: AHashSet<PathBuf>,
+    /// Samples the hit objects of some difficulty can load.
+    pub hitsounds: AHashSet<PathBuf>,
+    /// Custom samples used by osu!mania difficulties.
+    pub keysounds: AHashSet<PathBuf>,
+    /// Song audio referenced by `[General] AudioFilename`.
+    pub protected: AHashSet<PathBuf>,
+    pub beatmap_sets: AHashSet<PathBuf>,
+    /// Sets with a `.osu` or `.osb` file that could not be read, so their
+    /// references are incomplete.
+    pub unparsed_sets: AHashSet<PathBuf>,
+    pub beatmaps: Vec<Beatmap>,
+}
+
+impl ScanContext {
+    /// Returns the beatmap set folder (a directory holding `.osu` files)
+    /// that contains `path`, if any.
+    pub fn beatmap_set_of(&self, path: &Path) -> Option<&Path> {
+        path.ancestors()
+            .skip(1)
+            .find_map(|dir| self.beatmap_sets.get(dir).map(PathBuf::as_path))
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -43,11 +319,146 @@ pub struct CategorySummaryResponse {
     pub background_video: CategoryDetailSimple,
     pub background_image: CategoryDetailSimple,
     pub storyboard: CategoryDetailSimple,
+    pub storyboard_audio: CategoryDetailSimple,
     pub skin_element: CategoryDetailSimple,
     pub hitsound: CategoryDetailSimple,
+    pub keysound: CategoryDetailSimple,
+    pub unreferenced: CategoryDetailSimple,
+    pub protected: CategoryDetailSimple,
+    /// Category totals per game mode. A file in a set with several modes
+    /// counts towards each of them.
+    pub by_mode: BTreeMap<GameMode, BTreeMap<String, CategoryDetailSimple>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryDataResponse {
     pub files: Vec<FileInfo>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeatmapsResponse {
+    pub beatmaps: Vec<Beatmap>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeatmapSetInfo {
+    pub folder: PathBuf,
+    pub set_id: Option<i64>,
+    pub title: String,
+    pub artist: String,
+    /// Modes of the set's difficulties, e.g. `[osu, mania]` for a mixed set.
+    pub modes: Vec<GameMode>,
+    pub total_size: u64,
+    /// Size of the files in categories that can be deleted.
+    pub reclaimable_size: u64,
+    pub categories: BTreeMap<String, CategoryDetailSimple>,
+}
+
+impl BeatmapSetInfo {
+    fn new(folder: &Path) -> Self {
+        Self {
+            folder: folder.to_owned(),
+            set_id: None,
+            title: String::new(),
+            artist: String::new(),
+            modes: Vec::new(),
+            total_size: 0,
+            reclaimable_size: 0,
+            categories: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetSortKey {
+    #[default]
+    TotalSize,
+    ReclaimableSize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeatmapSetsResponse {
+    pub sets: Vec<BeatmapSetInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedDeletion {
+    pub path: PathBuf,
+    pub size: u64,
+    pub category: String,
+    pub beatmap_set: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DeletionPlan {
+    pub files: Vec<PlannedDeletion>,
+    pub total_size: u64,
+    pub total_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedDeletion {
+    pub file: FileInfo,
+    /// The `std::io::ErrorKind` of the failure, e.g. `PermissionDenied`.
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedDeletion {
+    pub file: FileInfo,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DeletionReport {
+    pub succeeded: Vec<FileInfo>,
+    pub failed: Vec<FailedDeletion>,
+    pub skipped: Vec<SkippedDeletion>,
+    pub freed_bytes: u64,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletionProgress {
+    pub category: String,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub original_path: PathBuf,
+    /// Location inside the quarantine directory, relative to it.
+    pub quarantined_path: PathBuf,
+    pub size: u64,
+    pub category: String,
+    /// Seconds since the Unix epoch at which the file was quarantined.
+    pub timestamp: u64,
+    /// A placeholder was written at `original_path` in place of the file.
+    #[serde(default)]
+    pub replaced: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct QuarantineManifest {
+    pub root: PathBuf,
+    pub entries: Vec<QuarantineEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedQuarantineEntry {
+    pub entry: QuarantineEntry,
+    /// The `std::io::ErrorKind` of the failure, e.g. `PermissionDenied`.
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct QuarantineActionResponse {
+    pub total_size: u64,
+    pub total_count: u64,
+    /// Entries that could not be restored or purged and stay in quarantine.
+    pub failed: Vec<FailedQuarantineEntry>,
+}
@@ -0,0 +1,326 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use ahash::{AHashMap, AHashSet};
+use unicode_normalization::UnicodeNormalization;
+
+/// Lexically resolves `.` and `..` components without touching the disk.
+/// Returns `None` when a `..` would climb above the start of the path.
+pub fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+/// Resolves file references found in beatmap files and keeps every resolved
+/// path confined to the scanned root.
+pub struct PathResolver {
+    root: PathBuf,
+    canonical_root: PathBuf,
+    /// Files under the root keyed the way osu! compares names, see `lookup_key`.
+    files: AHashMap<String, Vec<PathBuf>>,
+    /// Names of the files directly inside each directory, by directory key.
+    dir_files: AHashMap<String, Vec<String>>,
+    /// Keys of the directories directly inside each directory.
+    subdirs: AHashMap<String, AHashSet<String>>,
+}
+
+impl PathResolver {
+    pub fn new(root: &Path) -> io::Result<Self> {
+        Ok(Self {
+            root: normalize(root).unwrap_or_else(|| root.to_owned()),
+            canonical_root: fs::canonicalize(root)?,
+            files: AHashMap::new(),
+            dir_files: AHashMap::new(),
+            subdirs: AHashMap::new(),
+        })
+    }
+
+    /// Records the files found on disk so references can be matched against
+    /// them regardless of case and separators.
+    pub fn index_files<'a>(&mut self, paths: impl IntoIterator<Item = &'a Path>) {
+        for path in paths {
+            if let Some(key) = self.lookup_key(path) {
+                self.index_directories(&key);
+                self.files.entry(key).or_default().push(path.to_owned());
+            }
+        }
+        for candidates in self.files.values_mut() {
+            candidates.sort_unstable();
+        }
+    }
+
+    fn index_directories(&mut self, key: &str) {
+        let (dir, name) = key.rsplit_once('/').unwrap_or(("", key));
+        self.dir_files
+            .entry(dir.to_owned())
+            .or_default()
+            .push(name.to_owned());
+
+        // Stop at the first ancestor that already knows about its child
+        let mut child = dir;
+        while !child.is_empty() {
+            let parent = child.rsplit_once('/').map_or("", |(parent, _)| parent);
+            if !self
+                .subdirs
+                .entry(parent.to_owned())
+                .or_default()
+                .insert(child.to_owned())
+            {
+                break;
+            }
+            child = parent;
+        }
+    }
+
+    /// Case-folded, NFC-normalised path relative to the root with `/`
+    /// separators, so `SB\Star.png` and `sb/star.png` share a key.
+    fn lookup_key(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let key = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Some(key.nfc().collect::<String>().to_lowercase().nfc().collect())
+    }
+
+    /// Lower-cased names of the indexed files anywhere under `dir`.
+    pub fn file_names_under(&self, dir: &Path) -> Vec<&str> {
+        let key = match self.lookup_key(dir) {
+            Some(key) => key,
+            None => return Vec::new(),
+        };
+
+        let mut names = Vec::new();
+        let mut pending = vec![key.as_str()];
+        while let Some(dir) = pending.pop() {
+            if let Some(files) = self.dir_files.get(dir) {
+                names.extend(files.iter().map(String::as_str));
+            }
+            if let Some(subdirs) = self.subdirs.get(dir) {
+                pending.extend(subdirs.iter().map(String::as_str));
+            }
+        }
+        names
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Joins `reference` onto `parent`, rejecting absolute references and
+    /// anything that escapes the root, either lexically or through symlinks.
+    /// Backslashes count as separators and names are matched against the
+    /// indexed files the way osu! on Windows would, returning the on-disk path.
+    ///
+    /// Only indexed files resolve. The walk never follows symlinked
+    /// directories, so anything else is not a file under the root, and only
+    /// real files pay for a `canonicalize`.
+    pub fn resolve(&self, parent: &Path, reference: &str) -> Option<PathBuf> {
+        let reference = reference.replace('\\', "/");
+        let reference = Path::new(&reference);
+        if reference
+            .components()
+            .any(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+        {
+            return None;
+        }
+
+        let resolved = normalize(&parent.join(reference))?;
+        if !resolved.starts_with(&self.root) {
+            return None;
+        }
+        let candidates = self.files.get(&self.lookup_key(&resolved)?)?;
+        // Prefer an exact match when names only differ in case
+        let resolved = if candidates.contains(&resolved) {
+            resolved
+        } else {
+            candidates.first()?.clone()
+        };
+        // Indexed files can still be symlinks themselves
+        if let Ok(real) = fs::canonicalize(&resolved) {
+            if !real.starts_with(&self.canonical_root) {
+                return None;
+            }
+        }
+        Some(resolved)
+    }
+
+    /// Whether `path` really lives under the root once symlinks are resolved.
+    pub fn is_within_root(&self, path: &Path) -> io::Result<bool> {
+        Ok(fs::canonicalize(path)?.starts_with(&self.canonical_root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils::TempDir;
+
+    /// A root holding `set/bg.png` and `other/bg.png`, with `outside/` next
+    /// to it.
+    fn resolver(temp: &TempDir) -> PathResolver {
+        let set_bg = temp.write("Songs/set/bg.png", "");
+        let other_bg = temp.write("Songs/other/bg.png", "");
+        temp.write("outside/secret.png", "");
+
+        let mut resolver = PathResolver::new(&temp.path().join("Songs")).unwrap();
+        resolver.index_files([set_bg.as_path(), other_bg.as_path()]);
+        resolver
+    }
+
+    #[test]
+    fn resolves_references_inside_the_root() {
+        let temp = TempDir::new();
+        let resolver = resolver(&temp);
+        let set = temp.path().join("Songs/set");
+
+        assert_eq!(resolver.resolve(&set, "bg.png"), Some(set.join("bg.png")));
+        assert_eq!(
+            resolver.resolve(&set, "./sb/../bg.png"),
+            Some(set.join("bg.png"))
+        );
+        assert_eq!(
+            resolver.resolve(&set, "../other/bg.png"),
+            Some(temp.path().join("Songs/other/bg.png"))
+        );
+    }
+
+    #[test]
+    fn rejects_parent_escapes() {
+        let temp = TempDir::new();
+        let resolver = resolver(&temp);
+        let set = temp.path().join("Songs/set");
+
+        assert_eq!(resolver.resolve(&set, "../../outside/secret.png"), None);
+        assert_eq!(resolver.resolve(&set, r"..\..\outside\secret.png"), None);
+        assert_eq!(resolver.resolve(&set, r"..\../outside/secret.png"), None);
+        assert_eq!(resolver.resolve(&set, "../../../../../../etc/passwd"), None);
+    }
+
+    #[test]
+    fn rejects_absolute_references() {
+        let temp = TempDir::new();
+        let resolver = resolver(&temp);
+        let set = temp.path().join("Songs/set");
+
+        let secret = temp.path().join("outside/secret.png");
+        assert_eq!(resolver.resolve(&set, &secret.to_string_lossy()), None);
+        assert_eq!(resolver.resolve(&set, "/etc/passwd"), None);
+        assert_eq!(resolver.resolve(&set, r"\etc\passwd"), None);
+        assert_eq!(resolver.resolve(&set, r"C:\Windows\win.ini"), None);
+        assert_eq!(resolver.resolve(&set, "C:/Windows/win.ini"), None);
+    }
+
+    #[test]
+    fn matches_names_regardless_of_case_and_separators() {
+        let temp = TempDir::new();
+        let star = temp.write("Songs/set/SB/Star.png", "");
+        let lower = temp.write("Songs/set/hit.wav", "");
+        let upper = temp.write("Songs/set/HIT.wav", "");
+        let mut resolver = PathResolver::new(&temp.path().join("Songs")).unwrap();
+        resolver.index_files([star.as_path(), lower.as_path(), upper.as_path()]);
+        let set = temp.path().join("Songs/set");
+
+        assert_eq!(resolver.resolve(&set, r"sb\star.PNG"), Some(star.clone()));
+        assert_eq!(resolver.resolve(&set, "Sb/STAR.png"), Some(star));
+        // An exact match wins when names only differ in case
+        assert_eq!(resolver.resolve(&set, "hit.wav"), Some(lower));
+        assert_eq!(resolver.resolve(&set, "HIT.wav"), Some(upper));
+    }
+
+    #[test]
+    fn matches_names_regardless_of_unicode_normalization() {
+        let temp = TempDir::new();
+        // Decomposed, as macOS stores names
+        let decomposed = temp.write("Songs/set/Poke\u{301}mon.jpg", "");
+        let mut resolver = PathResolver::new(&temp.path().join("Songs")).unwrap();
+        resolver.index_files([decomposed.as_path()]);
+        let set = temp.path().join("Songs/set");
+
+        assert_eq!(
+            resolver.resolve(&set, "pok\u{E9}mon.JPG"),
+            Some(decomposed.clone())
+        );
+        assert_eq!(
+            resolver.resolve(&set, "Poke\u{301}mon.jpg"),
+            Some(decomposed)
+        );
+    }
+
+    #[test]
+    fn lists_file_names_under_a_directory() {
+        let temp = TempDir::new();
+        let files = [
+            temp.write("Songs/set/BG.png", ""),
+            temp.write("Songs/set/sb/deep/Star.png", ""),
+            temp.write("Songs/other/bg.png", ""),
+        ];
+        let mut resolver = PathResolver::new(&temp.path().join("Songs")).unwrap();
+        resolver.index_files(files.iter().map(PathBuf::as_path));
+
+        let mut names = resolver.file_names_under(&temp.path().join("Songs/set"));
+        names.sort_unstable();
+        assert_eq!(names, ["bg.png", "star.png"]);
+        let names = resolver.file_names_under(&temp.path().join("Songs/set/sb"));
+        assert_eq!(names, ["star.png"]);
+        assert_eq!(resolver.file_names_under(temp.path()).len(), 0);
+        assert_eq!(
+            resolver.file_names_under(&temp.path().join("Songs")).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn ignores_files_that_are_not_indexed() {
+        let temp = TempDir::new();
+        let resolver = resolver(&temp);
+        let set = temp.path().join("Songs/set");
+        temp.write("Songs/set/unindexed.png", "");
+
+        assert_eq!(resolver.resolve(&set, "missing.png"), None);
+        assert_eq!(resolver.resolve(&set, "unindexed.png"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_leading_outside() {
+        let temp = TempDir::new();
+        let resolver = resolver(&temp);
+        let set = temp.path().join("Songs/set");
+        std::os::unix::fs::symlink(temp.path().join("outside"), set.join("link")).unwrap();
+
+        assert_eq!(resolver.resolve(&set, "link/secret.png"), None);
+        assert!(!resolver
+            .is_within_root(&set.join("link/secret.png"))
+            .unwrap());
+    }
+
+    #[test]
+    fn checks_paths_are_within_root() {
+        let temp = TempDir::new();
+        let resolver = resolver(&temp);
+
+        assert!(resolver
+            .is_within_root(&temp.path().join("Songs/set/bg.png"))
+            .unwrap());
+        assert!(!resolver
+            .is_within_root(&temp.path().join("outside/secret.png"))
+            .unwrap());
+        assert!(resolver
+            .is_within_root(&temp.path().join("Songs/set/missing.png"))
+            .is_err());
+    }
+}
@@ -6,7 +6,6 @@ use ahash::AHashSet;
 pub struct FilePatterns {
     pub video_extensions: AHashSet<&'static str>,
     pub skin_patterns: AHashSet<&'static str>,
-    pub hitsound_patterns: AHashSet<&'static str>,
 }
 
 impl FilePatterns {
@@ -76,12 +75,9 @@ impl FilePatterns {
         .iter()
         .cloned()
         .collect();
-        let hitsound_patterns: AHashSet<_> =
-            ["drum-", "normal-", "soft-"].iter().cloned().collect();
         Self {
             video_extensions,
             skin_patterns,
-            hitsound_patterns,
         }
     }
 
@@ -102,11 +98,11 @@ impl FilePatterns {
     }
 
     #[inline]
-    pub fn is_hitsound(&self, name: &str) -> bool {
-        let name_lower = name.to_lowercase();
-        self.hitsound_patterns
-            .iter()
-            .any(|pattern| name_lower.contains(pattern))
+    pub fn is_beatmap_file(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext == "osu")
+            .unwrap_or(false)
     }
 
     #[inline]
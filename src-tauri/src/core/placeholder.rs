@@ -0,0 +1,58 @@
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+use anyhow::Result;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+
+use crate::core::consts::placeholder;
+
+/// Image written over replaced backgrounds, encoded lazily once per format so
+/// every file keeps the format its extension suggests.
+pub struct Placeholder {
+    image: DynamicImage,
+    encoded: Mutex<AHashMap<ImageFormat, Arc<Vec<u8>>>>,
+}
+
+impl Placeholder {
+    pub fn solid(color: [u8; 3]) -> Self {
+        Self::new(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            placeholder::SIZE,
+            placeholder::SIZE,
+            Rgb(color),
+        )))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Ok(Self::new(DynamicImage::ImageRgb8(
+            image::open(path)?.into_rgb8(),
+        )))
+    }
+
+    fn new(image: DynamicImage) -> Self {
+        Self {
+            image,
+            encoded: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    pub fn encode_for(&self, path: &Path) -> io::Result<Arc<Vec<u8>>> {
+        let format = ImageFormat::from_path(path)
+            .map_err(|e| io::Error::new(io::ErrorKind::Unsupported, e))?;
+        if let Some(bytes) = self.encoded.lock().unwrap().get(&format) {
+            return Ok(Arc::clone(bytes));
+        }
+
+        let mut bytes = Vec::new();
+        self.image
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .map_err(|e| io::Error::new(io::ErrorKind::Unsupported, e))?;
+        let bytes = Arc::new(bytes);
+        self.encoded
+            .lock()
+            .unwrap()
+            .insert(format, Arc::clone(&bytes));
+        Ok(bytes)
+    }
+}
@@ -1,24 +1,255 @@
 use std::cell::RefCell;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use ahash::AHashMap;
-use anyhow::Result;
+use ahash::{AHashMap, AHashSet};
+use anyhow::{bail, Result};
 use rayon::prelude::*;
 use tauri::{AppHandle, Emitter};
 use walkdir::WalkDir;
 
-use crate::core::consts::{deletion, scanner, status_values};
-use crate::core::counters::{CommonCounterState, FilterCounterState};
+use crate::core::cancellation::CancellationToken;
+use crate::core::consts::{deletion, placeholder, scanner, status_values, storyboard};
+use crate::core::counters::{CommonCounterState, DeletionCounterState, FilterCounterState};
+use crate::core::encoding;
+use crate::core::events::{self, Event, Variables};
+use crate::core::hitsounds::HitsoundReferences;
+use crate::core::layout::OsuLayout;
 use crate::core::models::{
-    CategoryDataResponse, CategoryDetailSimple, CategorySummaryResponse, FileInfo, FileType,
-    ScanContext, ScanResult,
+    Beatmap, BeatmapSetsResponse, BeatmapsResponse, CategoryDataResponse, CategoryDetailSimple,
+    CategorySummaryResponse, DeletionMode, DeletionPlan, DeletionProgress, DeletionReport,
+    FailedDeletion, FileInfo, FileType, GameMode, PlannedDeletion, QuarantineActionResponse,
+    ScanContext, ScanResult, SetSortKey, SkippedDeletion,
 };
+use crate::core::paths::{self, PathResolver};
 use crate::core::patterns::FilePatterns;
+use crate::core::placeholder::Placeholder;
+use crate::core::quarantine::Quarantine;
+use crate::core::trash;
+
+/// Long-running operations that must not overlap with each other.
+#[derive(Debug, Clone, Copy)]
+enum Operation {
+    Scan,
+    Deletion,
+    Quarantine,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Scan => write!(f, "scan"),
+            Operation::Deletion => write!(f, "deletion"),
+            Operation::Quarantine => write!(f, "quarantine operation"),
+        }
+    }
+}
+
+/// Marks an operation as running until dropped.
+struct OperationGuard<'a>(&'a Mutex<Option<Operation>>);
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+enum RemovalOutcome {
+    /// The file is gone, freeing the given number of bytes.
+    Removed(u64),
+    /// A placeholder, described by the new `FileInfo`, took the file's place.
+    Replaced(FileInfo, u64),
+    Skipped(&'static str),
+    Failed(std::io::Error),
+}
+
+/// Restricts a request to beatmap sets whose difficulties are all in one of
+/// the requested modes, e.g. osu!standard-only sets. Without modes every
+/// file matches.
+struct ModeFilter {
+    modes: Option<Vec<GameMode>>,
+    set_modes: AHashMap<PathBuf, Vec<GameMode>>,
+}
+
+impl ModeFilter {
+    fn new(modes: Option<Vec<GameMode>>, scan_result: &ScanResult) -> Self {
+        let set_modes = match modes {
+            Some(_) => scan_result
+                .sets
+                .iter()
+                .map(|set| (set.folder.clone(), set.modes.clone()))
+                .collect(),
+            None => AHashMap::new(),
+        };
+        Self { modes, set_modes }
+    }
+
+    /// Files outside any beatmap set have no mode and only match when no
+    /// modes were requested, as do sets none of whose difficulties parsed.
+    fn matches(&self, file: &FileInfo) -> bool {
+        let modes = match &self.modes {
+            Some(modes) => modes,
+            None => return true,
+        };
+        file.beatmap_set
+            .as_ref()
+            .and_then(|set| self.set_modes.get(set))
+            .is_some_and(|set_modes| {
+                !set_modes.is_empty() && set_modes.iter().all(|mode| modes.contains(mode))
+            })
+    }
+}
+
+/// Per-run state shared by every file removed in a single deletion request.
+struct DeletionContext {
+    mode: DeletionMode,
+    quarantine: Option<Quarantine>,
+    resolver: PathResolver,
+    replacement: Option<Placeholder>,
+    cancel: CancellationToken,
+}
+
+impl DeletionContext {
+    fn new(root: &Path, mode: DeletionMode, cancel: CancellationToken) -> Result<Self> {
+        Ok(Self {
+            mode,
+            quarantine: match mode {
+                DeletionMode::Quarantine => Some(Quarantine::open(root)?),
+                _ => None,
+            },
+            resolver: PathResolver::new(root)?,
+            replacement: None,
+            cancel,
+        })
+    }
+
+    /// Writes `placeholder` in place of every disposed file instead of
+    /// leaving it missing.
+    fn with_replacement(self, placeholder: Placeholder) -> Self {
+        Self {
+            replacement: Some(placeholder),
+            ..self
+        }
+    }
+
+    fn dispose(&self, file: &FileInfo, file_type: FileType) -> RemovalOutcome {
+        if self.cancel.is_cancelled() {
+            return RemovalOutcome::Skipped("cancelled");
+        }
+
+        match self.resolver.is_within_root(&file.path) {
+            Err(e) => return RemovalOutcome::Failed(e),
+            Ok(false) => return RemovalOutcome::Skipped("outside the scanned root"),
+            Ok(true) => {}
+        }
+
+        match std::fs::metadata(&file.path) {
+            Err(e) => RemovalOutcome::Failed(e),
+            Ok(metadata) if !file.is_unchanged(&metadata) => {
+                RemovalOutcome::Skipped("changed since the scan")
+            }
+            Ok(_) => match &self.replacement {
+                Some(placeholder) => match self.replace_file(file, file_type, placeholder) {
+                    Ok(replacement) => {
+                        let freed = file.size.saturating_sub(replacement.size);
+                        RemovalOutcome::Replaced(replacement, freed)
+                    }
+                    Err(e) => RemovalOutcome::Failed(e),
+                },
+                None => match self.remove_file(file, file_type) {
+                    Ok(()) => RemovalOutcome::Removed(file.size),
+                    Err(e) => RemovalOutcome::Failed(e),
+                },
+            },
+        }
+    }
+
+    fn replace_file(
+        &self,
+        file: &FileInfo,
+        file_type: FileType,
+        placeholder: &Placeholder,
+    ) -> std::io::Result<FileInfo> {
+        // Encode first so an unsupported format never touches the original
+        let bytes = placeholder.encode_for(&file.path)?;
+
+        // Write next to the original and rename over it, so a failed write
+        // (e.g. a full disk) never leaves a truncated image behind
+        let (temp_path, mut temp_file) = self.create_temp_file(&file.path)?;
+        let replaced = temp_file
+            .write_all(bytes.as_slice())
+            .and_then(|_| {
+                drop(temp_file);
+                if self.mode != DeletionMode::Permanent {
+                    self.remove_file(file, file_type)?;
+                }
+                std::fs::rename(&temp_path, &file.path)
+            })
+            .and_then(|_| std::fs::metadata(&file.path));
+        match replaced {
+            Ok(metadata) => Ok(FileInfo {
+                beatmap_set: file.beatmap_set.clone(),
+                ..FileInfo::from_metadata(file.path.clone(), &metadata)
+            }),
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Creates a fresh file next to `path`, never touching an existing one.
+    fn create_temp_file(&self, path: &Path) -> std::io::Result<(PathBuf, File)> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        for attempt in 0.. {
+            let temp_path = path.with_file_name(format!(".{}.{}.tmp", name, attempt));
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temp_path)
+            {
+                Ok(file) => return Ok((temp_path, file)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    fn remove_file(&self, file: &FileInfo, file_type: FileType) -> std::io::Result<()> {
+        match (self.mode, &self.quarantine) {
+            (DeletionMode::Permanent, _) => std::fs::remove_file(&file.path),
+            (DeletionMode::Trash, _) => trash::move_to_trash(&file.path),
+            (DeletionMode::Quarantine, Some(quarantine)) => {
+                quarantine.insert(file, file_type, self.replacement.is_some())
+            }
+            (DeletionMode::Quarantine, None) => {
+                Err(std::io::Error::other("quarantine is not open"))
+            }
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    fn finish(self, app: &AppHandle, report: &mut DeletionReport) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            report.cancelled = true;
+            app.emit(deletion::STATUS, status_values::CANCELLED)
+                .unwrap();
+        }
+        if let Some(quarantine) = self.quarantine {
+            quarantine.save()?;
+        }
+        Ok(())
+    }
+}
 
 pub struct FileProcessor {
     emit_interval: u64,
@@ -26,6 +257,10 @@ pub struct FileProcessor {
     scan_counters: Arc<CommonCounterState>,
     parse_counters: Arc<CommonCounterState>,
     filter_counters: Arc<FilterCounterState>,
+    deletion_counters: Arc<DeletionCounterState>,
+    scan_cancel: CancellationToken,
+    deletion_cancel: CancellationToken,
+    running: Mutex<Option<Operation>>,
     scan_result: Arc<RwLock<Option<ScanResult>>>,
 }
 
@@ -38,9 +273,32 @@ impl FileProcessor {
             scan_counters: Arc::new(CommonCounterState::new()),
             parse_counters: Arc::new(CommonCounterState::new()),
             filter_counters: Arc::new(FilterCounterState::new()),
+            deletion_counters: Arc::new(DeletionCounterState::new()),
+            scan_cancel: CancellationToken::new(),
+            deletion_cancel: CancellationToken::new(),
+            running: Mutex::new(None),
         }
     }
 
+    /// Claims the processor for `operation`, failing if another one is still
+    /// running. Queries never need this and keep serving the last result.
+    ///
+    /// The cancellation token is reset here, before any disk work, so a
+    /// cancel sent while the operation is still setting up is not lost.
+    fn begin(&self, operation: Operation) -> Result<OperationGuard<'_>> {
+        let mut running = self.running.lock().unwrap();
+        if let Some(current) = *running {
+            bail!("{} in progress", current);
+        }
+        match operation {
+            Operation::Scan => self.scan_cancel.reset(),
+            Operation::Deletion => self.deletion_cancel.reset(),
+            Operation::Quarantine => {}
+        }
+        *running = Some(operation);
+        Ok(OperationGuard(&self.running))
+    }
+
     #[inline]
     fn try_emit_scan_counts(&self, app: &AppHandle, force: bool) {
         if force {
@@ -128,12 +386,61 @@ impl FileProcessor {
         }
     }
 
-    fn parse_file<F>(&self, path: &Path, context: &mut ScanContext, parser: F) -> Result<()>
+    #[inline]
+    fn try_emit_deletion_progress(&self, app: &AppHandle, category: &str, force: bool) {
+        if force {
+            let (count, bytes) = self.deletion_counters.get_and_reset();
+            if count > 0 {
+                app.emit(
+                    deletion::PROGRESS,
+                    DeletionProgress {
+                        category: category.to_owned(),
+                        count,
+                        bytes,
+                    },
+                )
+                .unwrap();
+            }
+        } else {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros() as u64;
+
+            let last = self.last_emit.load(Ordering::Relaxed);
+            if now >= last + self.emit_interval {
+                if self
+                    .last_emit
+                    .compare_exchange(last, now, Ordering::Release, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let (count, bytes) = self.deletion_counters.get_and_reset();
+                    if count > 0 {
+                        app.emit(
+                            deletion::PROGRESS,
+                            DeletionProgress {
+                                category: category.to_owned(),
+                                count,
+                                bytes,
+                            },
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_file<F>(
+        &self,
+        path: &Path,
+        resolver: &PathResolver,
+        context: &mut ScanContext,
+        parser: F,
+    ) -> Result<()>
     where
         F: Fn(&str, &Path, &mut ScanContext) -> Option<()>,
     {
-        let file = File::open(path)?;
-        let reader = BufReader::with_capacity(128 * 1024, file); // 128 KB buffer
         let parent = path.parent().ok_or_else(|| {
             std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
@@ -141,59 +448,202 @@ impl FileProcessor {
             )
         })?;
 
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                parser(&line, parent, context);
-            }
+        // Decoded as a whole so every line of a legacy file gets the same
+        // encoding, picked by the names of the files in its folder
+        let bytes = std::fs::read(path)?;
+        let text = encoding::decode_file(&bytes, || resolver.file_names_under(parent));
+        for line in text.lines() {
+            parser(line, parent, context);
         }
         Ok(())
     }
 
-    fn parse_osu_file(&self, path: &Path, context: &mut ScanContext) -> Result<()> {
-        let in_events = RefCell::new(false);
-        self.parse_file(path, context, |line, parent, context| match line.trim() {
-            "[Events]" => {
-                *in_events.borrow_mut() = true;
-                None
+    fn parse_osu_file(
+        &self,
+        path: &Path,
+        resolver: &PathResolver,
+        context: &mut ScanContext,
+    ) -> Result<()> {
+        let section = RefCell::new(String::new());
+        let hitsounds = RefCell::new(HitsoundReferences::default());
+        let beatmap = RefCell::new(Beatmap::new(path));
+        self.parse_file(path, resolver, context, |line, parent, context| {
+            if let Some(name) = self.section_name(line) {
+                *section.borrow_mut() = name.to_owned();
+                return None;
             }
-            _ if !*in_events.borrow() => None,
-            _ if line.starts_with('[') => {
-                *in_events.borrow_mut() = false;
-                None
+
+            match section.borrow().as_str() {
+                section @ ("General" | "Metadata" | "Difficulty") => {
+                    let (key, value) = line.split_once(':')?;
+                    let (key, value) = (key.trim(), value.trim());
+                    beatmap.borrow_mut().set_field(section, key, value);
+                    if section == "General" {
+                        self.parse_general_field(key, value, parent, resolver, context, &hitsounds);
+                    } else if section == "Difficulty" {
+                        hitsounds.borrow_mut().add_difficulty_line(key, value);
+                    }
+                    None
+                }
+                "TimingPoints" => hitsounds.borrow_mut().add_timing_point(line),
+                "HitObjects" => hitsounds.borrow_mut().add_hit_object(line),
+                // Difficulty-specific storyboards live in the same section
+                "Events" => self.parse_event_line(line, parent, resolver, context),
+                _ => None,
             }
-            _ if line.starts_with("0,0,\"") || line.starts_with("Video,") => {
-                self.extract_quoted_path(line)
-                    .map(|file_path| context.backgrounds.insert(parent.join(file_path)));
-                None
+        })?;
+
+        let beatmap = beatmap.into_inner();
+        if let Some(parent) = path.parent() {
+            let samples = hitsounds.into_inner().into_sample_files();
+            for file_name in samples.hitsounds {
+                if let Some(sample_path) = resolver.resolve(parent, &file_name) {
+                    context.hitsounds.insert(sample_path);
+                }
             }
-            _ => None,
-        })
+            // Custom samples are keysounds in osu!mania and part of the chart
+            for file_name in samples.custom {
+                if let Some(sample_path) = resolver.resolve(parent, &file_name) {
+                    match beatmap.mode {
+                        GameMode::Mania => context.keysounds.insert(sample_path),
+                        _ => context.hitsounds.insert(sample_path),
+                    };
+                }
+            }
+        }
+        context.beatmaps.push(beatmap);
+        Ok(())
     }
 
-    fn parse_storyboard_file(&self, path: &Path, context: &mut ScanContext) -> Result<()> {
-        self.parse_file(path, context, |line, parent, context| {
-            if line.starts_with("Sprite,") {
-                self.extract_quoted_path(line).map(|sprite_path| {
-                    context.storyboard_elements.insert(parent.join(sprite_path))
-                });
+    /// Picks up the song audio so it is never mistaken for a hitsound or
+    /// skin element, e.g. a track named `soft-intro.mp3`.
+    fn parse_general_field(
+        &self,
+        key: &str,
+        value: &str,
+        parent: &Path,
+        resolver: &PathResolver,
+        context: &mut ScanContext,
+        hitsounds: &RefCell<HitsoundReferences>,
+    ) -> Option<()> {
+        if key == "AudioFilename" {
+            let audio_path = resolver.resolve(parent, value)?;
+            context.protected.insert(audio_path);
+        } else {
+            hitsounds.borrow_mut().add_general_line(key, value);
+        }
+        None
+    }
+
+    fn parse_storyboard_file(
+        &self,
+        path: &Path,
+        resolver: &PathResolver,
+        context: &mut ScanContext,
+    ) -> Result<()> {
+        let section = RefCell::new(String::new());
+        let variables = RefCell::new(Variables::default());
+        self.parse_file(path, resolver, context, |line, parent, context| {
+            if let Some(name) = self.section_name(line) {
+                *section.borrow_mut() = name.to_owned();
+                return None;
+            }
+            if section.borrow().as_str() == "Variables" {
+                return variables.borrow_mut().define(line);
             }
-            None
+
+            let line = variables.borrow().substitute(line);
+            self.parse_event_line(&line, parent, resolver, context)
         })
     }
 
-    fn extract_quoted_path<'a>(&self, line: &'a str) -> Option<&'a str> {
-        let start = line.find('"')?;
-        let end = line[start + 1..].find('"')?;
-        Some(&line[start + 1..start + 1 + end])
+    /// Returns `Events` for a `[Events]` header line.
+    fn section_name<'a>(&self, line: &'a str) -> Option<&'a str> {
+        line.trim().strip_prefix('[')?.strip_suffix(']')
+    }
+
+    /// Handles an event line, shared by `.osb` files and the `[Events]`
+    /// section of `.osu` files.
+    fn parse_event_line(
+        &self,
+        line: &str,
+        parent: &Path,
+        resolver: &PathResolver,
+        context: &mut ScanContext,
+    ) -> Option<()> {
+        match events::parse_event(line)? {
+            Event::Background { path } | Event::Video { path } => {
+                context.backgrounds.insert(resolver.resolve(parent, path)?);
+            }
+            Event::Sprite { path } => {
+                context
+                    .storyboard_elements
+                    .insert(resolver.resolve(parent, path)?);
+            }
+            Event::Animation { path, frame_count } => {
+                let frame_count = frame_count.min(storyboard::MAX_ANIMATION_FRAMES);
+                for frame_path in self.animation_frame_paths(path, frame_count) {
+                    if let Some(frame_path) = resolver.resolve(parent, &frame_path) {
+                        context.storyboard_elements.insert(frame_path);
+                    }
+                }
+            }
+            Event::Sample { path } => {
+                context
+                    .storyboard_audio
+                    .insert(resolver.resolve(parent, path)?);
+            }
+        }
+        None
+    }
+
+    /// osu! loads `sb/anim.png` as `sb/anim0.png`, `sb/anim1.png`, ...
+    fn animation_frame_paths(&self, path: &str, frame_count: usize) -> Vec<String> {
+        let name_start = path.rfind(['/', '\\']).map_or(0, |index| index + 1);
+        let (stem, extension) = match path[name_start..].rfind('.') {
+            Some(index) => path.split_at(name_start + index),
+            None => (path, ""),
+        };
+        (0..frame_count)
+            .map(|frame| format!("{}{}{}", stem, frame, extension))
+            .collect()
     }
 
     pub fn scan_directory(&self, app: &AppHandle, path: &Path) -> Result<()> {
         println!("Scanning requested for {:?}", path);
+        let _guard = self.begin(Operation::Scan)?;
+
+        // Never let skin patterns loose on the user's own skins
+        let path = match OsuLayout::detect(path) {
+            OsuLayout::Skins => {
+                bail!(
+                    "{:?} is inside the osu! Skins folder, scan Songs instead",
+                    path
+                )
+            }
+            OsuLayout::InstallRoot { songs } => {
+                app.emit(
+                    scanner::WARNING,
+                    format!(
+                        "{:?} is an osu! installation, only {:?} will be scanned",
+                        path, songs
+                    ),
+                )
+                .unwrap();
+                songs
+            }
+            OsuLayout::Other => path.to_owned(),
+        };
+
+        // Every reference and deletion is confined to this root
+        let mut resolver = PathResolver::new(&path)?;
+        let root = resolver.root().to_owned();
 
         app.emit(scanner::STATUS, status_values::SCAN_START)
             .unwrap();
-        let entries: Vec<_> = WalkDir::new(path)
+        let entries: Vec<_> = WalkDir::new(&root)
             .into_iter()
+            .take_while(|_| !self.scan_cancel.is_cancelled())
             .par_bridge()
             .filter_map(|e| {
                 if let Ok(e) = e {
@@ -207,6 +657,8 @@ impl FileProcessor {
             })
             .collect();
         self.try_emit_scan_counts(app, true); // Flush remaining counts
+        self.check_scan_cancelled(app)?;
+        resolver.index_files(entries.iter().map(|entry| entry.path()));
 
         app.emit(scanner::STATUS, status_values::PARSE_START)
             .unwrap();
@@ -225,12 +677,27 @@ impl FileProcessor {
             .fold(
                 || ScanContext::default(),
                 |mut context, entry| {
+                    if self.scan_cancel.is_cancelled() {
+                        return context;
+                    }
                     if let Some(ext) = entry.path().extension().and_then(|ext| ext.to_str()) {
-                        let _ = match ext {
-                            "osu" => self.parse_osu_file(entry.path(), &mut context),
-                            "osb" => self.parse_storyboard_file(entry.path(), &mut context),
+                        let parsed = match ext {
+                            "osu" => {
+                                if let Some(parent) = entry.path().parent() {
+                                    context.beatmap_sets.insert(parent.to_owned());
+                                }
+                                self.parse_osu_file(entry.path(), &resolver, &mut context)
+                            }
+                            "osb" => {
+                                self.parse_storyboard_file(entry.path(), &resolver, &mut context)
+                            }
                             _ => Ok(()),
                         };
+                        if parsed.is_err() {
+                            if let Some(parent) = entry.path().parent() {
+                                context.unparsed_sets.insert(parent.to_owned());
+                            }
+                        }
                     }
                     context
                 },
@@ -240,10 +707,28 @@ impl FileProcessor {
                 |mut a, b| {
                     a.backgrounds.extend(b.backgrounds);
                     a.storyboard_elements.extend(b.storyboard_elements);
+                    a.storyboard_audio.extend(b.storyboard_audio);
+                    a.hitsounds.extend(b.hitsounds);
+                    a.keysounds.extend(b.keysounds);
+                    a.protected.extend(b.protected);
+                    a.beatmap_sets.extend(b.beatmap_sets);
+                    a.unparsed_sets.extend(b.unparsed_sets);
+                    a.beatmaps.extend(b.beatmaps);
                     a
                 },
             );
         self.try_emit_parse_counts(app, true); // Flush remaining counts
+        self.check_scan_cancelled(app)?;
+        if !scan_context.unparsed_sets.is_empty() {
+            app.emit(
+                scanner::WARNING,
+                format!(
+                    "{} beatmap sets could not be analysed and are left out of cleanup",
+                    scan_context.unparsed_sets.len()
+                ),
+            )
+            .unwrap();
+        }
 
         app.emit(scanner::STATUS, status_values::FILTER_START)
             .unwrap();
@@ -251,6 +736,10 @@ impl FileProcessor {
         let scan_result = entries
             .par_iter()
             .filter_map(|entry| {
+                if self.scan_cancel.is_cancelled() {
+                    return None;
+                }
+
                 let path = entry.path();
                 let file_type = self.categorize_file(&patterns, &app, path, &scan_context);
 
@@ -260,14 +749,11 @@ impl FileProcessor {
 
                 entry.metadata().ok().map(|metadata| {
                     let size = metadata.len();
-                    (
-                        file_type,
-                        FileInfo {
-                            path: path.to_owned(),
-                            size,
-                        },
-                        size,
-                    )
+                    let file_info = FileInfo {
+                        beatmap_set: scan_context.beatmap_set_of(path).map(Path::to_path_buf),
+                        ..FileInfo::from_metadata(path.to_owned(), &metadata)
+                    };
+                    (file_type, file_info, size)
                 })
             })
             .fold(
@@ -286,11 +772,37 @@ impl FileProcessor {
                 a
             });
         self.try_emit_filter_counts(app, true); // Flush remaining counts
+        self.check_scan_cancelled(app)?;
 
+        let mut beatmaps = scan_context.beatmaps;
+        beatmaps.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        let mut scan_result = ScanResult {
+            root,
+            beatmaps,
+            ..scan_result
+        };
+        scan_result.refresh_sets();
         *self.scan_result.write().unwrap() = Some(scan_result);
         Ok(())
     }
 
+    /// Aborts the scan if it was cancelled, leaving the previous result intact.
+    fn check_scan_cancelled(&self, app: &AppHandle) -> Result<()> {
+        if self.scan_cancel.is_cancelled() {
+            app.emit(scanner::STATUS, status_values::CANCELLED).unwrap();
+            bail!("scan cancelled");
+        }
+        Ok(())
+    }
+
+    pub fn cancel_scan(&self) {
+        self.scan_cancel.cancel();
+    }
+
+    pub fn cancel_deletion(&self) {
+        self.deletion_cancel.cancel();
+    }
+
     fn categorize_file(
         &self,
         patterns: &FilePatterns,
@@ -298,27 +810,50 @@ impl FileProcessor {
         path: &Path,
         context: &ScanContext,
     ) -> FileType {
-        let file_type = if patterns.is_video(path) {
+        let file_type = self.file_type_of(patterns, path, context);
+        self.filter_counters.increment(file_type);
+        self.try_emit_filter_counts(app, false);
+        file_type
+    }
+
+    fn file_type_of(
+        &self,
+        patterns: &FilePatterns,
+        path: &Path,
+        context: &ScanContext,
+    ) -> FileType {
+        let set = context.beatmap_set_of(path);
+        if set.is_none() {
+            // Beatmap categories only apply inside beatmap set folders
+            FileType::Other
+        } else if patterns.is_beatmap_file(path) || context.protected.contains(path) {
+            FileType::Protected
+        } else if patterns.is_video(path) {
             FileType::BackgroundVideo
         } else if context.backgrounds.contains(path) {
             FileType::BackgroundImage
         } else if context.storyboard_elements.contains(path) || patterns.is_storyboard_file(path) {
             FileType::Storyboard
-        } else if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if patterns.is_skin_element(name) {
-                FileType::SkinElement
-            } else if patterns.is_hitsound(name) {
-                FileType::Hitsound
-            } else {
-                FileType::Other
-            }
-        } else {
+        } else if context.keysounds.contains(path) {
+            // Before hitsounds, so a shared sample never goes with that category
+            FileType::Keysound
+        } else if context.hitsounds.contains(path) {
+            // Before storyboard audio, which often reuses gameplay samples
+            FileType::Hitsound
+        } else if context.storyboard_audio.contains(path) {
+            FileType::StoryboardAudio
+        } else if set.is_some_and(|set| context.unparsed_sets.contains(set)) {
+            // Whatever the unreadable file references would look unused
             FileType::Other
-        };
-
-        self.filter_counters.increment(file_type);
-        self.try_emit_filter_counts(app, false);
-        file_type
+        } else if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| patterns.is_skin_element(name))
+        {
+            FileType::SkinElement
+        } else {
+            FileType::Unreferenced
+        }
     }
 
     pub fn get_category_summary(&self) -> Option<CategorySummaryResponse> {
@@ -341,6 +876,32 @@ impl FileProcessor {
             })
             .collect();
 
+        let set_modes: AHashMap<&Path, &[GameMode]> = scan_result
+            .sets
+            .iter()
+            .map(|set| (set.folder.as_path(), set.modes.as_slice()))
+            .collect();
+        let mut by_mode: BTreeMap<GameMode, BTreeMap<String, CategoryDetailSimple>> =
+            BTreeMap::new();
+        for (file_type, files) in &scan_result.files {
+            for file in files {
+                let modes = file
+                    .beatmap_set
+                    .as_deref()
+                    .and_then(|set| set_modes.get(set).copied())
+                    .unwrap_or_default();
+                for mode in modes {
+                    let detail = by_mode
+                        .entry(*mode)
+                        .or_default()
+                        .entry(file_type.category().to_owned())
+                        .or_default();
+                    detail.total_size += file.size;
+                    detail.total_count += 1;
+                }
+            }
+        }
+
         Some(CategorySummaryResponse {
             background_video: store
                 .get(&FileType::BackgroundVideo)
@@ -354,11 +915,22 @@ impl FileProcessor {
                 .get(&FileType::Storyboard)
                 .cloned()
                 .unwrap_or_default(),
+            storyboard_audio: store
+                .get(&FileType::StoryboardAudio)
+                .cloned()
+                .unwrap_or_default(),
             skin_element: store
                 .get(&FileType::SkinElement)
                 .cloned()
                 .unwrap_or_default(),
             hitsound: store.get(&FileType::Hitsound).cloned().unwrap_or_default(),
+            keysound: store.get(&FileType::Keysound).cloned().unwrap_or_default(),
+            unreferenced: store
+                .get(&FileType::Unreferenced)
+                .cloned()
+                .unwrap_or_default(),
+            protected: store.get(&FileType::Protected).cloned().unwrap_or_default(),
+            by_mode,
         })
     }
 
@@ -370,59 +942,479 @@ impl FileProcessor {
             None => return None,
         };
 
-        let files = scan_result.files.get(&match category {
-            "background_video" => FileType::BackgroundVideo,
-            "background_image" => FileType::BackgroundImage,
-            "storyboard" => FileType::Storyboard,
-            "skin_element" => FileType::SkinElement,
-            "hitsound" => FileType::Hitsound,
-            _ => return None,
-        });
+        let files = scan_result.files.get(&FileType::from_category(category)?);
 
         Some(CategoryDataResponse {
             files: files.cloned().unwrap_or_default(),
         })
     }
 
-    pub fn delete_files(&self, app: &AppHandle, categories: Vec<&str>) -> Result<()> {
-        let scan_result = self.get_scan_result();
-        let mut scan_result = scan_result.write().unwrap();
+    pub fn get_beatmaps(&self) -> Option<BeatmapsResponse> {
+        let binding = self.get_scan_result();
+        let scan_result = binding.read().unwrap();
+        let scan_result = match &*scan_result {
+            Some(scan_result) => scan_result,
+            None => return None,
+        };
 
-        let scan_result = match &mut *scan_result {
+        Some(BeatmapsResponse {
+            beatmaps: scan_result.beatmaps.clone(),
+        })
+    }
+
+    /// Lists beatmap sets, largest first by `sort_by`.
+    pub fn get_sets(&self, sort_by: SetSortKey) -> Option<BeatmapSetsResponse> {
+        let binding = self.get_scan_result();
+        let scan_result = binding.read().unwrap();
+        let scan_result = match &*scan_result {
             Some(scan_result) => scan_result,
-            None => return Ok(()),
+            None => return None,
         };
 
-        categories.iter().for_each(|category| {
-            let file_type = match *category {
-                "background_video" => FileType::BackgroundVideo,
-                "background_image" => FileType::BackgroundImage,
-                "storyboard" => FileType::Storyboard,
-                "skin_element" => FileType::SkinElement,
-                "hitsound" => FileType::Hitsound,
-                _ => return,
+        let mut sets = scan_result.sets.clone();
+        sets.sort_unstable_by(|a, b| {
+            let (a_size, b_size) = match sort_by {
+                SetSortKey::TotalSize => (a.total_size, b.total_size),
+                SetSortKey::ReclaimableSize => (a.reclaimable_size, b.reclaimable_size),
             };
+            b_size.cmp(&a_size).then_with(|| a.folder.cmp(&b.folder))
+        });
+        Some(BeatmapSetsResponse { sets })
+    }
 
-            println!(
-                "Deleting files for category {:?}: {:?}",
-                category, file_type
-            );
+    pub fn plan_deletion(
+        &self,
+        categories: Vec<&str>,
+        modes: Option<Vec<GameMode>>,
+    ) -> Option<DeletionPlan> {
+        let binding = self.get_scan_result();
+        let scan_result = binding.read().unwrap();
+        let scan_result = match &*scan_result {
+            Some(scan_result) => scan_result,
+            None => return None,
+        };
 
-            app.emit(deletion::CATEGORY_START, category).unwrap();
-            if let Some(files) = scan_result.files.get_mut(&file_type) {
-                files.par_drain(..).for_each(|file| {
-                    if let Err(e) = std::fs::remove_file(&file.path) {
-                        eprintln!("Failed to delete file {:?}: {}", file.path, e);
-                    }
+        let filter = ModeFilter::new(modes, scan_result);
+        let mut plan = DeletionPlan::default();
+        for file_type in categories.into_iter().filter_map(FileType::from_category) {
+            let files = match scan_result.files.get(&file_type) {
+                Some(files) => files,
+                None => continue,
+            };
+
+            for file in files.iter().filter(|file| filter.matches(file)) {
+                plan.total_size += file.size;
+                plan.total_count += 1;
+                plan.files.push(PlannedDeletion {
+                    path: file.path.clone(),
+                    size: file.size,
+                    category: file_type.category().to_owned(),
+                    beatmap_set: file.beatmap_set.clone(),
                 });
             }
-            app.emit(deletion::CATEGORY_COMPLETE, category).unwrap();
-        });
+        }
+        // Keep the plan stable so two runs can be diffed
+        plan.files.sort_unstable_by(|a, b| a.path.cmp(&b.path));
 
-        Ok(())
+        Some(plan)
+    }
+
+    pub fn delete_files(
+        &self,
+        app: &AppHandle,
+        categories: Vec<&str>,
+        mode: DeletionMode,
+        modes: Option<Vec<GameMode>>,
+    ) -> Result<DeletionReport> {
+        let _guard = self.begin(Operation::Deletion)?;
+        let mut report = DeletionReport::default();
+
+        let binding = self.get_scan_result();
+        let (root, filter) = match &*binding.read().unwrap() {
+            Some(scan_result) => (
+                scan_result.root.clone(),
+                ModeFilter::new(modes, scan_result),
+            ),
+            None => return Ok(report),
+        };
+
+        let deletion = DeletionContext::new(&root, mode, self.deletion_cancel.clone())?;
+        let file_types: Vec<FileType> = categories
+            .into_iter()
+            .filter_map(FileType::from_category)
+            .collect();
+        let selected = self.take_files(&file_types, |file| filter.matches(file));
+
+        let mut remaining = Vec::new();
+        for (file_type, files) in selected {
+            if deletion.is_cancelled() {
+                remaining.push((file_type, files));
+                continue;
+            }
+            let files = self.remove_files(app, file_type, files, &deletion, &mut report);
+            remaining.push((file_type, files));
+        }
+        self.return_files(remaining, report.freed_bytes);
+
+        deletion.finish(app, &mut report)?;
+        Ok(report)
+    }
+
+    pub fn replace_backgrounds(
+        &self,
+        app: &AppHandle,
+        image: Option<&Path>,
+        color: Option<[u8; 3]>,
+        mode: DeletionMode,
+    ) -> Result<DeletionReport> {
+        let _guard = self.begin(Operation::Deletion)?;
+        let placeholder = match image {
+            Some(image) => Placeholder::from_file(image)?,
+            None => Placeholder::solid(color.unwrap_or(placeholder::DEFAULT_COLOR)),
+        };
+
+        let mut report = DeletionReport::default();
+        let binding = self.get_scan_result();
+        let root = match &*binding.read().unwrap() {
+            Some(scan_result) => scan_result.root.clone(),
+            None => return Ok(report),
+        };
+
+        let deletion = DeletionContext::new(&root, mode, self.deletion_cancel.clone())?
+            .with_replacement(placeholder);
+        let file_type = FileType::BackgroundImage;
+        let remaining = self
+            .take_files(&[file_type], |_| true)
+            .into_iter()
+            .map(|(file_type, files)| {
+                let files = self.remove_files(app, file_type, files, &deletion, &mut report);
+                (file_type, files)
+            })
+            .collect();
+        self.return_files(remaining, report.freed_bytes);
+
+        deletion.finish(app, &mut report)?;
+        Ok(report)
+    }
+
+    pub fn delete_paths(
+        &self,
+        app: &AppHandle,
+        paths: Vec<PathBuf>,
+        mode: DeletionMode,
+    ) -> Result<DeletionReport> {
+        let _guard = self.begin(Operation::Deletion)?;
+        let mut report = DeletionReport::default();
+
+        // Protected files are only looked up to explain why they were skipped
+        let binding = self.get_scan_result();
+        let (root, file_types) = match &*binding.read().unwrap() {
+            Some(scan_result) => (
+                scan_result.root.clone(),
+                scan_result
+                    .files
+                    .keys()
+                    .copied()
+                    .filter(|file_type| *file_type != FileType::Protected)
+                    .collect::<Vec<_>>(),
+            ),
+            None => return Ok(report),
+        };
+
+        let deletion = DeletionContext::new(&root, mode, self.deletion_cancel.clone())?;
+        let mut requested: AHashSet<PathBuf> = paths.into_iter().collect();
+        let selected = self.take_files(&file_types, |file| requested.remove(&file.path));
+
+        let mut remaining = Vec::new();
+        for (file_type, files) in selected {
+            if files.is_empty() || deletion.is_cancelled() {
+                remaining.push((file_type, files));
+                continue;
+            }
+            let files = self.remove_files(app, file_type, files, &deletion, &mut report);
+            remaining.push((file_type, files));
+        }
+        self.return_files(remaining, report.freed_bytes);
+
+        let scan_result = binding.read().unwrap();
+        let protected = scan_result
+            .as_ref()
+            .and_then(|scan_result| scan_result.files.get(&FileType::Protected));
+        for path in requested {
+            let protected = protected.and_then(|files| files.iter().find(|file| file.path == path));
+            report.skipped.push(match protected {
+                Some(file) => SkippedDeletion {
+                    file: file.clone(),
+                    reason: "protected beatmap file".to_owned(),
+                },
+                None => SkippedDeletion {
+                    file: FileInfo {
+                        path,
+                        ..Default::default()
+                    },
+                    reason: "not part of the current scan result".to_owned(),
+                },
+            });
+        }
+        drop(scan_result);
+
+        deletion.finish(app, &mut report)?;
+        Ok(report)
+    }
+
+    /// Takes the files of `file_types` matching `select` out of the scan
+    /// result. The write lock is only held here and in `return_files`, so
+    /// queries keep being answered while the files are removed.
+    fn take_files<F>(
+        &self,
+        file_types: &[FileType],
+        mut select: F,
+    ) -> Vec<(FileType, Vec<FileInfo>)>
+    where
+        F: FnMut(&FileInfo) -> bool,
+    {
+        let binding = self.get_scan_result();
+        let mut scan_result = binding.write().unwrap();
+        let scan_result = match &mut *scan_result {
+            Some(scan_result) => scan_result,
+            None => return Vec::new(),
+        };
+
+        file_types
+            .iter()
+            .map(|&file_type| {
+                let files = scan_result.files.entry(file_type).or_default();
+                let (selected, kept): (Vec<_>, Vec<_>) =
+                    std::mem::take(files).into_iter().partition(&mut select);
+                *files = kept;
+                (file_type, selected)
+            })
+            .collect()
+    }
+
+    /// Puts the files that are still on disk back into the scan result.
+    fn return_files(&self, remaining: Vec<(FileType, Vec<FileInfo>)>, freed_bytes: u64) {
+        let binding = self.get_scan_result();
+        let mut scan_result = binding.write().unwrap();
+        let scan_result = match &mut *scan_result {
+            Some(scan_result) => scan_result,
+            None => return,
+        };
+
+        for (file_type, files) in remaining {
+            scan_result
+                .files
+                .entry(file_type)
+                .or_default()
+                .extend(files);
+        }
+        scan_result.total_size -= freed_bytes;
+        scan_result.refresh_sets();
+    }
+
+    /// Removes `files` and records each outcome in `report`, returning the
+    /// files that are still on disk so the caller can keep them visible.
+    fn remove_files(
+        &self,
+        app: &AppHandle,
+        file_type: FileType,
+        files: Vec<FileInfo>,
+        deletion: &DeletionContext,
+        report: &mut DeletionReport,
+    ) -> Vec<FileInfo> {
+        let category = file_type.category();
+        println!(
+            "Deleting {} files for category {:?}: {:?} ({:?})",
+            files.len(),
+            category,
+            file_type,
+            deletion.mode
+        );
+
+        app.emit(deletion::CATEGORY_START, category).unwrap();
+        let outcomes: Vec<_> = files
+            .into_par_iter()
+            .map(|file| {
+                let outcome = deletion.dispose(&file, file_type);
+                if let RemovalOutcome::Removed(freed) | RemovalOutcome::Replaced(_, freed) = outcome
+                {
+                    self.deletion_counters.increment(freed);
+                    self.try_emit_deletion_progress(app, category, false);
+                }
+                (file, outcome)
+            })
+            .collect();
+
+        let mut remaining = Vec::new();
+        for (file, outcome) in outcomes {
+            match outcome {
+                RemovalOutcome::Removed(freed) => {
+                    report.freed_bytes += freed;
+                    report.succeeded.push(file);
+                }
+                RemovalOutcome::Replaced(replacement, freed) => {
+                    report.freed_bytes += freed;
+                    report.succeeded.push(file);
+                    remaining.push(replacement);
+                }
+                RemovalOutcome::Skipped(reason) => {
+                    remaining.push(file.clone());
+                    report.skipped.push(SkippedDeletion {
+                        file,
+                        reason: reason.to_owned(),
+                    });
+                }
+                RemovalOutcome::Failed(e) => {
+                    remaining.push(file.clone());
+                    report.failed.push(FailedDeletion {
+                        file,
+                        kind: format!("{:?}", e.kind()),
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
+        self.try_emit_deletion_progress(app, category, true); // Flush remaining counts
+        app.emit(deletion::CATEGORY_COMPLETE, category).unwrap();
+
+        remaining
+    }
+
+    pub fn restore_quarantine(
+        &self,
+        root: &Path,
+        category: Option<&str>,
+        set: Option<&str>,
+    ) -> Result<QuarantineActionResponse> {
+        let _guard = self.begin(Operation::Quarantine)?;
+        Quarantine::open(&self.quarantine_root(root))?.restore(category, set)
+    }
+
+    pub fn purge_quarantine(
+        &self,
+        root: &Path,
+        grace_period_secs: u64,
+    ) -> Result<QuarantineActionResponse> {
+        let _guard = self.begin(Operation::Quarantine)?;
+        Quarantine::open(&self.quarantine_root(root))?.purge(grace_period_secs)
+    }
+
+    /// The root a scan of `path` used, which is where its quarantine lives.
+    fn quarantine_root(&self, path: &Path) -> PathBuf {
+        let root = match OsuLayout::detect(path) {
+            OsuLayout::InstallRoot { songs } => songs,
+            _ => path.to_owned(),
+        };
+        paths::normalize(&root).unwrap_or(root)
     }
 
     fn get_scan_result(&self) -> Arc<RwLock<Option<ScanResult>>> {
         Arc::clone(&self.scan_result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils::TempDir;
+
+    const BEATMAP: &str = "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+SampleSet: Soft
+
+[Difficulty]
+SliderMultiplier:1
+
+[Events]
+0,0,\"bg.jpg\",0,0
+Video,0,\"video.mp4\"
+
+[TimingPoints]
+0,500,4,2,1,100,1,0
+1000,-100,4,2,2,100,0,0
+3000,-100,4,3,3,100,0,0
+
+[HitObjects]
+0,0,0,2,0,L|100:0,2,100,0|0|8,0:0|0:0|0:0,0:0:0:0:
+256,192,2000,12,4,3000,0:0:0:0:
+256,192,3500,1,0,0:0:0:0:clap.wav
+";
+
+    const STORYBOARD: &str = "[Events]
+Sprite,Foreground,Centre,\"sb/star.png\",320,240
+Animation,Foreground,Centre,\"sb/anim.png\",320,240,2,100,LoopForever
+Sample,0,0,\"sb/boom.wav\",100
+Sample,500,0,\"soft-hitclap2.wav\",100
+";
+
+    #[test]
+    fn keeps_referenced_files_out_of_unreferenced() {
+        let temp = TempDir::new();
+        let set = temp.path().join("set");
+        let beatmap = temp.write("set/map.osu", BEATMAP);
+        let storyboard = temp.write("set/map.osb", STORYBOARD);
+        let expected = [
+            ("audio.mp3", FileType::Protected),
+            ("bg.jpg", FileType::BackgroundImage),
+            ("video.mp4", FileType::BackgroundVideo),
+            // Slider head, repeat and tail at 0, 500 and 1000
+            ("soft-hitnormal.wav", FileType::Hitsound),
+            ("soft-hitnormal2.wav", FileType::Hitsound),
+            // Also a storyboard sample, but gameplay needs it
+            ("soft-hitclap2.wav", FileType::Hitsound),
+            ("soft-slidertick2.wav", FileType::Hitsound),
+            // The spinner ends on the drum point
+            ("drum-hitfinish3.wav", FileType::Hitsound),
+            ("clap.wav", FileType::Hitsound),
+            ("sb/star.png", FileType::Storyboard),
+            ("sb/anim0.png", FileType::Storyboard),
+            ("sb/anim1.png", FileType::Storyboard),
+            ("sb/boom.wav", FileType::StoryboardAudio),
+            ("unused.png", FileType::Unreferenced),
+        ];
+        let mut paths = vec![beatmap.clone(), storyboard.clone()];
+        for (name, _) in expected {
+            paths.push(temp.write(&format!("set/{}", name), ""));
+        }
+
+        let processor = FileProcessor::new();
+        let mut resolver = PathResolver::new(temp.path()).unwrap();
+        resolver.index_files(paths.iter().map(PathBuf::as_path));
+        let mut context = ScanContext::default();
+        context.beatmap_sets.insert(set.clone());
+        processor
+            .parse_osu_file(&beatmap, &resolver, &mut context)
+            .unwrap();
+        processor
+            .parse_storyboard_file(&storyboard, &resolver, &mut context)
+            .unwrap();
+
+        let patterns = FilePatterns::new();
+        for (name, file_type) in expected {
+            assert_eq!(
+                processor.file_type_of(&patterns, &set.join(name), &context),
+                file_type,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn never_calls_files_of_unparsed_sets_unreferenced() {
+        let temp = TempDir::new();
+        let set = temp.path().join("set");
+        let song = temp.write("set/audio.mp3", "");
+
+        let mut context = ScanContext::default();
+        context.beatmap_sets.insert(set.clone());
+        context.unparsed_sets.insert(set);
+        let processor = FileProcessor::new();
+        assert_eq!(
+            processor.file_type_of(&FilePatterns::new(), &song, &context),
+            FileType::Other
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use crate::core::consts::quarantine;
+use crate::core::file_ops::move_file;
+use crate::core::models::{
+    FailedQuarantineEntry, FileInfo, FileType, QuarantineActionResponse, QuarantineEntry,
+    QuarantineManifest,
+};
+
+/// A restorable holding area living next to the scanned root. Files keep their
+/// layout relative to the root and are tracked in a JSON manifest. Every file
+/// is also journaled before it is moved, so a run that never gets to save the
+/// manifest can still be restored.
+pub struct Quarantine {
+    dir: PathBuf,
+    root: PathBuf,
+    manifest: Mutex<QuarantineManifest>,
+    journal: Mutex<Option<fs::File>>,
+}
+
+impl Quarantine {
+    pub fn open(root: &Path) -> Result<Self> {
+        let dir = Self::dir_for(root);
+        let manifest_path = dir.join(quarantine::MANIFEST_FILE);
+        let mut manifest = if manifest_path.exists() {
+            serde_json::from_slice(&fs::read(&manifest_path)?)?
+        } else {
+            QuarantineManifest {
+                root: root.to_owned(),
+                entries: Vec::new(),
+            }
+        };
+        manifest.entries.extend(Self::read_journal(&dir)?);
+
+        Ok(Self {
+            dir,
+            root: root.to_owned(),
+            manifest: Mutex::new(manifest),
+            journal: Mutex::new(None),
+        })
+    }
+
+    /// Entries left behind by a run that stopped before saving. Lines cut off
+    /// mid-write and files that never made it into quarantine are dropped.
+    fn read_journal(dir: &Path) -> Result<Vec<QuarantineEntry>> {
+        let file = match fs::File::open(dir.join(quarantine::JOURNAL_FILE)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let entry: QuarantineEntry = match serde_json::from_str(&line?) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            // A name reserved for a move that never happened is still empty
+            let quarantined_path = dir.join(&entry.quarantined_path);
+            match quarantined_path.symlink_metadata() {
+                Ok(metadata) if metadata.len() == entry.size => entries.push(entry),
+                Ok(_) if entry.original_path.exists() => {
+                    let _ = fs::remove_file(&quarantined_path);
+                }
+                _ => {}
+            }
+        }
+        Ok(entries)
+    }
+
+    fn dir_for(root: &Path) -> PathBuf {
+        match (root.parent(), root.file_name()) {
+            (Some(parent), Some(name)) => {
+                let mut name = name.to_owned();
+                name.push(quarantine::DIR_SUFFIX);
+                parent.join(name)
+            }
+            _ => root.join(quarantine::DIR_SUFFIX),
+        }
+    }
+
+    /// Moves `file` into quarantine. `replaced` marks files that get a
+    /// placeholder written in their place, which restoring overwrites.
+    pub fn insert(&self, file: &FileInfo, file_type: FileType, replaced: bool) -> io::Result<()> {
+        let relative = file.path.strip_prefix(&self.root).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} is not inside {:?}", file.path, self.root),
+            )
+        })?;
+        if let Some(parent) = self.dir.join(relative).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let relative = self.reserve_relative_path(relative)?;
+        let target = self.dir.join(&relative);
+
+        let entry = QuarantineEntry {
+            original_path: file.path.clone(),
+            quarantined_path: relative,
+            size: file.size,
+            category: file_type.category().to_owned(),
+            timestamp: now_secs(),
+            replaced,
+        };
+        let moved = self
+            .append_to_journal(&entry)
+            .and_then(|_| move_file(&file.path, &target));
+        if let Err(e) = moved {
+            let _ = fs::remove_file(&target);
+            return Err(e);
+        }
+
+        self.manifest.lock().unwrap().entries.push(entry);
+        Ok(())
+    }
+
+    fn append_to_journal(&self, entry: &QuarantineEntry) -> io::Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        let journal = match &mut *journal {
+            Some(journal) => journal,
+            None => journal.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.dir.join(quarantine::JOURNAL_FILE))?,
+            ),
+        };
+
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        journal.write_all(&line)
+    }
+
+    /// Picks a name that avoids clobbering an earlier quarantined copy of the
+    /// same file, creating it empty so concurrent inserts cannot pick it too.
+    /// The move then replaces the empty file.
+    fn reserve_relative_path(&self, relative: &Path) -> io::Result<PathBuf> {
+        let stem = relative
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = relative
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+
+        let mut candidate = relative.to_owned();
+        for attempt in 1.. {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.dir.join(&candidate))
+            {
+                Ok(_) => return Ok(candidate),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    candidate.set_file_name(format!("{}.{}{}", stem, attempt, extension));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    /// Moves matching entries back to their original location. Entries whose
+    /// original path is occupied again are left in quarantine, unless the file
+    /// there is the placeholder written when the entry was replaced.
+    pub fn restore(
+        &self,
+        category: Option<&str>,
+        set: Option<&str>,
+    ) -> Result<QuarantineActionResponse> {
+        let set_path = set.map(|set| self.root.join(set));
+        let mut response = QuarantineActionResponse::default();
+
+        self.retain_entries(|entry| {
+            let matches_category = category.is_none() || category == Some(entry.category.as_str());
+            let matches_set = match &set_path {
+                Some(set_path) => entry.original_path.starts_with(set_path),
+                None => true,
+            };
+            let occupied = !entry.replaced && entry.original_path.exists();
+            if !matches_category || !matches_set || occupied {
+                return true;
+            }
+
+            let restored = entry
+                .original_path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| {
+                    move_file(
+                        &self.dir.join(&entry.quarantined_path),
+                        &entry.original_path,
+                    )
+                });
+            match restored {
+                Ok(()) => {
+                    response.total_size += entry.size;
+                    response.total_count += 1;
+                    false
+                }
+                Err(e) => {
+                    response.failed.push(FailedQuarantineEntry::new(entry, e));
+                    true
+                }
+            }
+        })?;
+        Ok(response)
+    }
+
+    /// Permanently removes entries that have been quarantined for longer than
+    /// `grace_period_secs`.
+    pub fn purge(&self, grace_period_secs: u64) -> Result<QuarantineActionResponse> {
+        let now = now_secs();
+        let mut response = QuarantineActionResponse::default();
+
+        self.retain_entries(|entry| {
+            if entry.timestamp.saturating_add(grace_period_secs) > now {
+                return true;
+            }
+
+            match fs::remove_file(self.dir.join(&entry.quarantined_path)) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    response.failed.push(FailedQuarantineEntry::new(entry, e));
+                    return true;
+                }
+            }
+            response.total_size += entry.size;
+            response.total_count += 1;
+            false
+        })?;
+        Ok(response)
+    }
+
+    fn retain_entries<F>(&self, keep: F) -> Result<()>
+    where
+        F: FnMut(&QuarantineEntry) -> bool,
+    {
+        self.manifest.lock().unwrap().entries.retain(keep);
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let manifest = self.manifest.lock().unwrap();
+        if manifest.entries.is_empty() && !self.dir.exists() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let manifest_path = self.dir.join(quarantine::MANIFEST_FILE);
+        let temp_path = manifest_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(&*manifest)?)?;
+        fs::rename(&temp_path, &manifest_path)?;
+
+        // Everything journaled is in the manifest now
+        *self.journal.lock().unwrap() = None;
+        match fs::remove_file(self.dir.join(quarantine::JOURNAL_FILE)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FailedQuarantineEntry {
+    fn new(entry: &QuarantineEntry, error: io::Error) -> Self {
+        Self {
+            entry: entry.clone(),
+            kind: format!("{:?}", error.kind()),
+            message: error.to_string(),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
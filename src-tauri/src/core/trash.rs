@@ -0,0 +1,123 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::core::file_ops::move_file;
+
+/// Moves `path` into the freedesktop.org trash of the current user, writing
+/// the matching `.trashinfo` entry so file managers can restore it.
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    if !cfg!(all(unix, not(target_os = "macos"))) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the freedesktop trash is not available on this platform",
+        ));
+    }
+
+    let original = fs::canonicalize(path)?;
+    let trash = trash_home()?;
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let (name, mut info_file) = reserve_trash_name(&original, &files_dir, &info_dir)?;
+    let info_path = info_dir.join(format!("{}.trashinfo", name));
+    let trashed_path = files_dir.join(&name);
+
+    let result = write!(
+        info_file,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_trash_path(&original),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )
+    .and_then(|_| move_file(&original, &trashed_path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&info_path);
+    }
+    result
+}
+
+fn trash_home() -> io::Result<PathBuf> {
+    if let Some(data_home) = env::var_os("XDG_DATA_HOME").map(PathBuf::from) {
+        if data_home.is_absolute() {
+            return Ok(data_home.join("Trash"));
+        }
+    }
+
+    env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".local/share/Trash"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))
+}
+
+/// Picks a name that is free in both `files/` and `info/`, creating the
+/// `.trashinfo` file atomically so concurrent deletions cannot collide.
+fn reserve_trash_name(
+    original: &Path,
+    files_dir: &Path,
+    info_dir: &Path,
+) -> io::Result<(String, fs::File)> {
+    let file_name = original
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(index) if index > 0 => file_name.split_at(index),
+        _ => (file_name.as_str(), ""),
+    };
+
+    for attempt in 0.. {
+        let name = match attempt {
+            0 => file_name.clone(),
+            n => format!("{}.{}{}", stem, n, extension),
+        };
+        if files_dir.join(&name).symlink_metadata().is_ok() {
+            continue;
+        }
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(info_dir.join(format!("{}.trashinfo", name)))
+        {
+            Ok(file) => return Ok((name, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/// Percent-encodes an absolute path as required by the `Path=` key. The raw
+/// bytes are encoded so names that are not valid UTF-8 restore unchanged.
+fn encode_trash_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path_bytes(path).iter() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(path) => Cow::Borrowed(path.as_bytes()),
+        Cow::Owned(path) => Cow::Owned(path.into_bytes()),
+    }
+}
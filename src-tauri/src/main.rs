@@ -1,7 +1,7 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::{Builder, Manager};
 
 mod core;
@@ -19,8 +19,8 @@ fn main() {
                         .build(),
                 )?;
             }
-            app.manage(FileProcessorState(Mutex::new(FileProcessorService::new(
-                Arc::new(app.handle().clone()),
+            app.manage(FileProcessorState(FileProcessorService::new(Arc::new(
+                app.handle().clone(),
             ))));
             Ok(())
         })
@@ -28,7 +28,16 @@ fn main() {
             service::file_processor_service::scan_directory,
             service::file_processor_service::get_category_summary,
             service::file_processor_service::get_category_data,
-            service::file_processor_service::delete_files
+            service::file_processor_service::get_beatmaps,
+            service::file_processor_service::get_sets,
+            service::file_processor_service::plan_deletion,
+            service::file_processor_service::delete_files,
+            service::file_processor_service::delete_paths,
+            service::file_processor_service::replace_backgrounds,
+            service::file_processor_service::restore_quarantine,
+            service::file_processor_service::purge_quarantine,
+            service::file_processor_service::cancel_scan,
+            service::file_processor_service::cancel_deletion
         ])
         .plugin(tauri_plugin_dialog::init())
         .run(tauri::generate_context!())
@@ -1,10 +1,14 @@
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Result;
 use tauri::{AppHandle, State};
 
-use crate::core::models::{CategoryDataResponse, CategorySummaryResponse};
+use crate::core::consts::quarantine;
+use crate::core::models::{
+    BeatmapSetsResponse, BeatmapsResponse, CategoryDataResponse, CategorySummaryResponse,
+    DeletionMode, DeletionPlan, DeletionReport, GameMode, QuarantineActionResponse, SetSortKey,
+};
 use crate::core::processor::FileProcessor;
 
 pub struct FileProcessorService {
@@ -20,6 +24,14 @@ impl FileProcessorService {
         }
     }
 
+    pub fn cancel_scan(&self) {
+        self.file_processor.cancel_scan()
+    }
+
+    pub fn cancel_deletion(&self) {
+        self.file_processor.cancel_deletion()
+    }
+
     pub fn scan_directory(&self, path: &Path) -> Result<()> {
         let app = self.app.clone();
         self.file_processor.scan_directory(&app, path)
@@ -33,18 +45,76 @@ impl FileProcessorService {
         self.file_processor.get_category_data(category)
     }
 
-    pub fn delete_files(&self, categories: Vec<&str>) -> Result<()> {
+    pub fn get_beatmaps(&self) -> Option<BeatmapsResponse> {
+        self.file_processor.get_beatmaps()
+    }
+
+    pub fn get_sets(&self, sort_by: SetSortKey) -> Option<BeatmapSetsResponse> {
+        self.file_processor.get_sets(sort_by)
+    }
+
+    pub fn plan_deletion(
+        &self,
+        categories: Vec<&str>,
+        modes: Option<Vec<GameMode>>,
+    ) -> Option<DeletionPlan> {
+        self.file_processor.plan_deletion(categories, modes)
+    }
+
+    pub fn delete_files(
+        &self,
+        categories: Vec<&str>,
+        mode: DeletionMode,
+        modes: Option<Vec<GameMode>>,
+    ) -> Result<DeletionReport> {
+        let app = self.app.clone();
+        self.file_processor
+            .delete_files(&app, categories, mode, modes)
+    }
+
+    pub fn replace_backgrounds(
+        &self,
+        image: Option<&Path>,
+        color: Option<[u8; 3]>,
+        mode: DeletionMode,
+    ) -> Result<DeletionReport> {
+        let app = self.app.clone();
+        self.file_processor
+            .replace_backgrounds(&app, image, color, mode)
+    }
+
+    pub fn delete_paths(&self, paths: Vec<PathBuf>, mode: DeletionMode) -> Result<DeletionReport> {
         let app = self.app.clone();
-        self.file_processor.delete_files(&app, categories)
+        self.file_processor.delete_paths(&app, paths, mode)
+    }
+
+    pub fn restore_quarantine(
+        &self,
+        root: &Path,
+        category: Option<&str>,
+        set: Option<&str>,
+    ) -> Result<QuarantineActionResponse> {
+        self.file_processor.restore_quarantine(root, category, set)
+    }
+
+    pub fn purge_quarantine(
+        &self,
+        root: &Path,
+        grace_period_secs: u64,
+    ) -> Result<QuarantineActionResponse> {
+        self.file_processor
+            .purge_quarantine(root, grace_period_secs)
     }
 }
 
-pub struct FileProcessorState(pub Mutex<FileProcessorService>);
+/// Not wrapped in a mutex: the processor synchronises internally, so queries
+/// and cancellation stay responsive while a scan or deletion is running.
+pub struct FileProcessorState(pub FileProcessorService);
 
 #[tauri::command(async)]
 pub fn scan_directory(path: &str, state: State<'_, FileProcessorState>) -> Result<(), String> {
     let path = Path::new(&path);
-    let file_processor_service = state.0.lock().unwrap();
+    let file_processor_service = &state.0;
     file_processor_service
         .scan_directory(path)
         .map_err(|e| e.to_string())
@@ -54,7 +124,7 @@ pub fn scan_directory(path: &str, state: State<'_, FileProcessorState>) -> Resul
 pub fn get_category_summary(
     state: State<'_, FileProcessorState>,
 ) -> Result<Option<CategorySummaryResponse>, String> {
-    let file_processor_service = state.0.lock().unwrap();
+    let file_processor_service = &state.0;
     Ok(file_processor_service.get_category_summary())
 }
 
@@ -63,17 +133,112 @@ pub fn get_category_data(
     category: &str,
     state: State<'_, FileProcessorState>,
 ) -> Result<Option<CategoryDataResponse>, String> {
-    let file_processor_service = state.0.lock().unwrap();
+    let file_processor_service = &state.0;
     Ok(file_processor_service.get_category_data(category))
 }
 
+#[tauri::command(async)]
+pub fn get_beatmaps(
+    state: State<'_, FileProcessorState>,
+) -> Result<Option<BeatmapsResponse>, String> {
+    let file_processor_service = &state.0;
+    Ok(file_processor_service.get_beatmaps())
+}
+
+#[tauri::command(async)]
+pub fn get_sets(
+    sort_by: Option<SetSortKey>,
+    state: State<'_, FileProcessorState>,
+) -> Result<Option<BeatmapSetsResponse>, String> {
+    let file_processor_service = &state.0;
+    Ok(file_processor_service.get_sets(sort_by.unwrap_or_default()))
+}
+
+#[tauri::command(async)]
+pub fn plan_deletion(
+    categories: Vec<&str>,
+    modes: Option<Vec<GameMode>>,
+    state: State<'_, FileProcessorState>,
+) -> Result<Option<DeletionPlan>, String> {
+    let file_processor_service = &state.0;
+    Ok(file_processor_service.plan_deletion(categories, modes))
+}
+
 #[tauri::command(async)]
 pub fn delete_files(
     categories: Vec<&str>,
+    mode: DeletionMode,
+    modes: Option<Vec<GameMode>>,
+    state: State<'_, FileProcessorState>,
+) -> Result<DeletionReport, String> {
+    let file_processor_service = &state.0;
+    file_processor_service
+        .delete_files(categories, mode, modes)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn replace_backgrounds(
+    image: Option<&str>,
+    color: Option<[u8; 3]>,
+    mode: DeletionMode,
+    state: State<'_, FileProcessorState>,
+) -> Result<DeletionReport, String> {
+    let image = image.map(Path::new);
+    let file_processor_service = &state.0;
+    file_processor_service
+        .replace_backgrounds(image, color, mode)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn delete_paths(
+    paths: Vec<PathBuf>,
+    mode: DeletionMode,
     state: State<'_, FileProcessorState>,
-) -> Result<(), String> {
-    let file_processor_service = state.0.lock().unwrap();
+) -> Result<DeletionReport, String> {
+    let file_processor_service = &state.0;
     file_processor_service
-        .delete_files(categories)
+        .delete_paths(paths, mode)
         .map_err(|e| e.to_string())
 }
+
+#[tauri::command(async)]
+pub fn restore_quarantine(
+    path: &str,
+    category: Option<&str>,
+    set: Option<&str>,
+    state: State<'_, FileProcessorState>,
+) -> Result<QuarantineActionResponse, String> {
+    let path = Path::new(&path);
+    let file_processor_service = &state.0;
+    file_processor_service
+        .restore_quarantine(path, category, set)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(async)]
+pub fn purge_quarantine(
+    path: &str,
+    grace_period_secs: Option<u64>,
+    state: State<'_, FileProcessorState>,
+) -> Result<QuarantineActionResponse, String> {
+    let path = Path::new(&path);
+    let file_processor_service = &state.0;
+    file_processor_service
+        .purge_quarantine(
+            path,
+            grace_period_secs.unwrap_or(quarantine::DEFAULT_GRACE_PERIOD_SECS),
+        )
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn cancel_scan(state: State<'_, FileProcessorState>) {
+    state.0.cancel_scan();
+}
+
+#[tauri::command]
+pub fn cancel_deletion(state: State<'_, FileProcessorState>) {
+    state.0.cancel_deletion();
+}
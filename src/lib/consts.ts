@@ -3,16 +3,22 @@ export const scanner = {
 
     SCAN_COUNTS: "scanner_scan_counts",
     PARSE_COUNTS: "scanner_parse_counts",
-    FILTER_COUNTS: "scanner_filter_counts"
+    FILTER_COUNTS: "scanner_filter_counts",
+
+    WARNING: "scanner_warning"
 };
 
 export const deletion = {
+    STATUS: "deletion_status",
+
     CATEGORY_START: "deletion_category_start",
-    CATEGORY_COMPLETE: "deletion_category_complete"
+    CATEGORY_COMPLETE: "deletion_category_complete",
+    PROGRESS: "deletion_progress"
 };
 
 export const statusValues = {
     SCAN_START: "scan_start",
     PARSE_START: "parse_start",
-    FILTER_START: "filter_start"
+    FILTER_START: "filter_start",
+    CANCELLED: "cancelled"
 };
\ No newline at end of file
@@ -1,6 +1,17 @@
 import { invoke } from '@tauri-apps/api/core';
 
-import type { CategorySummaryResponse, CategoryDataResponse } from '$lib/utils/interfaces.ts';
+import type {
+    BeatmapSetsResponse,
+    BeatmapsResponse,
+    CategorySummaryResponse,
+    CategoryDataResponse,
+    DeletionMode,
+    DeletionPlan,
+    DeletionReport,
+    GameMode,
+    QuarantineActionResponse,
+    SetSortKey
+} from '$lib/utils/interfaces.ts';
 
 export class FileProcessorService {
     async scanDirectory(path: string): Promise<void> {
@@ -12,6 +23,15 @@ export class FileProcessorService {
         }
     }
 
+    async cancelScan(): Promise<void> {
+        try {
+            await invoke('cancel_scan');
+        } catch (error) {
+            console.log('Error while cancelling scan:', error);
+            throw error;
+        }
+    }
+
     async getCategorySummary(): Promise<CategorySummaryResponse> {
         try {
             return await invoke('get_category_summary');
@@ -30,12 +50,102 @@ export class FileProcessorService {
         }
     }
 
-    async deleteFiles(categories: string[]): Promise<void> {
+    async getBeatmaps(): Promise<BeatmapsResponse | null> {
+        try {
+            return await invoke('get_beatmaps');
+        } catch (error) {
+            console.log('Error while getting beatmaps:', error);
+            throw error;
+        }
+    }
+
+    async getSets(sortBy: SetSortKey = 'total_size'): Promise<BeatmapSetsResponse | null> {
         try {
-            await invoke('delete_files', { categories });
+            return await invoke('get_sets', { sortBy });
+        } catch (error) {
+            console.log('Error while getting beatmap sets:', error);
+            throw error;
+        }
+    }
+
+    async planDeletion(categories: string[], modes?: GameMode[]): Promise<DeletionPlan | null> {
+        try {
+            return await invoke('plan_deletion', { categories, modes });
+        } catch (error) {
+            console.log('Error while planning deletion:', error);
+            throw error;
+        }
+    }
+
+    async deleteFiles(
+        categories: string[],
+        mode: DeletionMode = 'permanent',
+        modes?: GameMode[]
+    ): Promise<DeletionReport> {
+        try {
+            return await invoke('delete_files', { categories, mode, modes });
         } catch (error) {
             console.log('Error while deleting files:', error);
             throw error;
         }
     }
+
+    async deletePaths(
+        paths: string[],
+        mode: DeletionMode = 'permanent'
+    ): Promise<DeletionReport> {
+        try {
+            return await invoke('delete_paths', { paths, mode });
+        } catch (error) {
+            console.log('Error while deleting paths:', error);
+            throw error;
+        }
+    }
+
+    async cancelDeletion(): Promise<void> {
+        try {
+            await invoke('cancel_deletion');
+        } catch (error) {
+            console.log('Error while cancelling deletion:', error);
+            throw error;
+        }
+    }
+
+    async replaceBackgrounds(
+        mode: DeletionMode = 'permanent',
+        image?: string,
+        color?: [number, number, number]
+    ): Promise<DeletionReport> {
+        try {
+            return await invoke('replace_backgrounds', { image, color, mode });
+        } catch (error) {
+            console.log('Error while replacing backgrounds:', error);
+            throw error;
+        }
+    }
+
+    async restoreQuarantine(
+        path: string,
+        category?: string,
+        set?: string
+    ): Promise<QuarantineActionResponse> {
+        try {
+            return await invoke('restore_quarantine', { path, category, set });
+        } catch (error) {
+            console.log('Error while restoring quarantine:', error);
+            throw error;
+        }
+    }
+
+    async purgeQuarantine(
+        path: string,
+        gracePeriodSecs?: number
+    ): Promise<QuarantineActionResponse> {
+        try {
+            return await invoke('purge_quarantine', { path, gracePeriodSecs });
+        } catch (error) {
+            console.log('Error while purging quarantine:', error);
+            throw error;
+        }
+    }
 }
\ No newline at end of file
@@ -10,8 +10,12 @@ const initialState: AnalyzerState = {
             background_videos: 0,
             background_images: 0,
             storyboards: 0,
+            storyboard_audio: 0,
             hitsounds: 0,
+            keysounds: 0,
             skin_elements: 0,
+            unreferenced: 0,
+            protected: 0,
             other: 0
         }
     },
@@ -48,8 +52,12 @@ const createAnalyzerState = () => {
         analyzer.counts.filtered.background_videos += counts.background_video || 0;
         analyzer.counts.filtered.background_images += counts.background_image || 0;
         analyzer.counts.filtered.storyboards += counts.storyboard || 0;
+        analyzer.counts.filtered.storyboard_audio += counts.storyboard_audio || 0;
         analyzer.counts.filtered.hitsounds += counts.hitsound || 0;
+        analyzer.counts.filtered.keysounds += counts.keysound || 0;
         analyzer.counts.filtered.skin_elements += counts.skin_element || 0;
+        analyzer.counts.filtered.unreferenced += counts.unreferenced || 0;
+        analyzer.counts.filtered.protected += counts.protected || 0;
         analyzer.counts.filtered.other += counts.other || 0;
     };
 
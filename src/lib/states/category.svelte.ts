@@ -1,5 +1,9 @@
 import { CategoryIconComponents } from "$lib/utils/icons";
-import type { CategoryState, CategorySummaryResponse } from "$lib/utils/interfaces";
+import type {
+    CategoryDetailSimple,
+    CategoryState,
+    CategorySummaryResponse
+} from "$lib/utils/interfaces";
 
 
 let initialState: CategoryState = {
@@ -30,6 +34,15 @@ let initialState: CategoryState = {
         size: 0,
         count: 0
     },
+    storyboard_audio: {
+        id: 'storyboard_audio',
+        icon: CategoryIconComponents['storyboard_audio'],
+        title: 'Storyboard Audio',
+        description: 'Remove storyboard sound effects',
+        selected: false,
+        size: 0,
+        count: 0
+    },
     hitsound: {
         id: 'hitsound',
         icon: CategoryIconComponents['hitsound'],
@@ -39,6 +52,24 @@ let initialState: CategoryState = {
         size: 0,
         count: 0
     },
+    keysound: {
+        id: 'keysound',
+        icon: CategoryIconComponents['keysound'],
+        title: 'Mania Keysounds',
+        description: 'Remove osu!mania keysounds (breaks those charts, only select if you are sure)',
+        selected: false,
+        size: 0,
+        count: 0
+    },
+    unreferenced: {
+        id: 'unreferenced',
+        icon: CategoryIconComponents['unreferenced'],
+        title: 'Unreferenced Files',
+        description: 'Remove files no difficulty or storyboard uses',
+        selected: false,
+        size: 0,
+        count: 0
+    },
     skin_element: {
         id: 'skin_element',
         icon: CategoryIconComponents['skin_element'],
@@ -61,8 +92,11 @@ const createCategoryState = () => {
     const updateCategory = (sumarry: CategorySummaryResponse) => {
         for (let key in sumarry) {
             let keyCategory = key as keyof CategorySummaryResponse;
-            categories[keyCategory].size = sumarry[keyCategory].total_size;
-            categories[keyCategory].count = sumarry[keyCategory].total_count;
+            // Protected files are reported but never offered for deletion
+            if (!(keyCategory in categories)) continue;
+            let detail = sumarry[keyCategory] as CategoryDetailSimple;
+            categories[keyCategory].size = detail.total_size;
+            categories[keyCategory].count = detail.total_count;
         }
     };
 
@@ -1,12 +1,27 @@
-import { FileMusic, FileQuestion, Image, Images, ScrollText, Video } from 'lucide-svelte';
+import {
+    AudioLines,
+    FileMusic,
+    FileQuestion,
+    FileX,
+    Image,
+    Images,
+    Keyboard,
+    ScrollText,
+    ShieldCheck,
+    Video
+} from 'lucide-svelte';
 import type { ComponentType } from 'svelte';
 
 const CategoryIconComponents: Record<string, ComponentType> = {
     background_video: Video,
     background_image: Image,
     storyboard: ScrollText,
+    storyboard_audio: AudioLines,
     hitsound: FileMusic,
+    keysound: Keyboard,
     skin_element: Images,
+    unreferenced: FileX,
+    protected: ShieldCheck,
     other: FileQuestion
 };
 
@@ -4,8 +4,12 @@ interface CounterUpdate {
     background_video?: number;
     background_image?: number;
     storyboard?: number;
+    storyboard_audio?: number;
     skin_element?: number;
     hitsound?: number;
+    keysound?: number;
+    unreferenced?: number;
+    protected?: number;
     other?: number;
 }
 
@@ -18,13 +22,132 @@ interface CategorySummaryResponse {
     background_video: CategoryDetailSimple;
     background_image: CategoryDetailSimple;
     storyboard: CategoryDetailSimple;
+    storyboard_audio: CategoryDetailSimple;
     skin_element: CategoryDetailSimple;
     hitsound: CategoryDetailSimple;
+    keysound: CategoryDetailSimple;
+    unreferenced: CategoryDetailSimple;
+    protected: CategoryDetailSimple;
+    by_mode: Partial<Record<GameMode, Record<string, CategoryDetailSimple>>>;
 }
 
 interface FileInfo {
     path: string;
     size: number;
+    modified: number | null;
+    inode: number | null;
+    beatmap_set: string | null;
+}
+
+type GameMode = 'osu' | 'taiko' | 'catch' | 'mania';
+
+interface BeatmapDifficulty {
+    hp_drain_rate: number | null;
+    circle_size: number | null;
+    overall_difficulty: number | null;
+    approach_rate: number | null;
+    slider_multiplier: number | null;
+    slider_tick_rate: number | null;
+}
+
+interface Beatmap {
+    path: string;
+    audio_filename: string | null;
+    mode: GameMode;
+    preview_time: number | null;
+    title: string;
+    title_unicode: string;
+    artist: string;
+    artist_unicode: string;
+    creator: string;
+    version: string;
+    source: string;
+    tags: string[];
+    beatmap_id: number | null;
+    beatmap_set_id: number | null;
+    difficulty: BeatmapDifficulty;
+}
+
+interface BeatmapsResponse {
+    beatmaps: Beatmap[];
+}
+
+interface BeatmapSetInfo {
+    folder: string;
+    set_id: number | null;
+    title: string;
+    artist: string;
+    total_size: number;
+    reclaimable_size: number;
+    categories: Record<string, CategoryDetailSimple>;
+    modes: GameMode[];
+}
+
+type SetSortKey = 'total_size' | 'reclaimable_size';
+
+interface BeatmapSetsResponse {
+    sets: BeatmapSetInfo[];
+}
+
+interface PlannedDeletion {
+    path: string;
+    size: number;
+    category: string;
+    beatmap_set: string | null;
+}
+
+interface DeletionPlan {
+    files: PlannedDeletion[];
+    total_size: number;
+    total_count: number;
+}
+
+interface FailedDeletion {
+    file: FileInfo;
+    kind: string;
+    message: string;
+}
+
+interface SkippedDeletion {
+    file: FileInfo;
+    reason: string;
+}
+
+interface DeletionReport {
+    succeeded: FileInfo[];
+    failed: FailedDeletion[];
+    skipped: SkippedDeletion[];
+    freed_bytes: number;
+    cancelled: boolean;
+}
+
+interface DeletionProgress {
+    category: string;
+    count: number;
+    bytes: number;
+}
+
+type DeletionMode = 'permanent' | 'trash' | 'quarantine';
+
+interface QuarantineEntry {
+    original_path: string;
+    quarantined_path: string;
+    size: number;
+    category: string;
+    timestamp: number;
+    replaced: boolean;
+}
+
+interface FailedQuarantineEntry {
+    entry: QuarantineEntry;
+    kind: string;
+    message: string;
+}
+
+interface QuarantineActionResponse {
+    total_size: number;
+    total_count: number;
+    failed: FailedQuarantineEntry[];
 }
 
 interface AlertType {
@@ -37,8 +160,12 @@ interface FilterCounts {
     background_videos: number;
     background_images: number;
     storyboards: number;
+    storyboard_audio: number;
     hitsounds: number;
+    keysounds: number;
     skin_elements: number;
+    unreferenced: number;
+    protected: number;
     other: number;
 }
 
@@ -88,9 +215,27 @@ interface CategoryDataResponse {
 
 export type {
     CounterUpdate,
+    CategoryDetailSimple,
     CategorySummaryResponse,
     CategoryDataResponse,
     FileInfo,
+    GameMode,
+    BeatmapDifficulty,
+    Beatmap,
+    BeatmapsResponse,
+    BeatmapSetInfo,
+    SetSortKey,
+    BeatmapSetsResponse,
+    PlannedDeletion,
+    DeletionPlan,
+    FailedDeletion,
+    SkippedDeletion,
+    DeletionReport,
+    DeletionProgress,
+    DeletionMode,
+    QuarantineEntry,
+    FailedQuarantineEntry,
+    QuarantineActionResponse,
     AlertType,
     FilterCounts,
     AnalyzerState,